@@ -1,12 +1,15 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::future::Future;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use actix_web::{HttpResponse, Responder, web};
+use actix_web::error::{ErrorBadRequest, ErrorNotFound};
 use actix_web::http::header::{ContentType};
-use mobc::Pool;
+use mobc::{Connection, Pool};
 use mobc_redis::redis::AsyncCommands;
 use mobc_redis::RedisConnectionManager;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use rayon::prelude::*;
 use redis::Commands;
 use serde::{Serialize};
@@ -16,22 +19,40 @@ use tracing::error;
 use tracing::log::{debug, info};
 use uuid::Uuid;
 
+use crate::bot::bot_strategy::{queue_bot_commands, BotStrategy};
+use crate::game::game_results::GameResults;
+use crate::game::map_renderer::{render_png, render_svg};
+use crate::game::replay::render_replay_gif;
+use crate::game::game_rules::{GameRules, MovementCostModel, SubmissionMode, TurnGate};
 use crate::game::game_state::{GameMap, GameState, GameStatus, RoundState};
 use crate::planet::direction::Direction;
-use crate::planet::map_generator::MapGenerator;
+use crate::planet::map_error::MapError;
+use crate::planet::map_fairness::FairnessReport;
+use crate::planet::map_file::MapFile;
+use crate::planet::map_generator::{CreatedMap, MapGenerator};
+use crate::planet::map_symmetry::MapSymmetry;
+use crate::planet::resource_profile::ResourceProfile;
+use crate::planet::map_topology::MapTopology;
+use crate::planet::pathfinding::find_cheapest_path;
 use crate::planet::planet::Planet;
+use crate::planet::planet_memory::PlanetMemory;
 use crate::planet::resource::Resource;
-use crate::player::{Money, PlayerState};
+use crate::player::{PlayerState, PlayerStatus};
 use crate::robot::robot::Robot;
+use crate::robot::robot_levels::RobotLevels;
+use crate::robot::standing_order::StandingOrder;
 use crate::trading::external::command::Command;
+use crate::trading::external::command_rejection::CommandRejection;
 use crate::trading::external::command_type::CommandType;
 use crate::trading::external::handler::battle_command_handler::{apply_damage_for_round, calculate_damage_for_round, delete_commands_for_dead_robots};
-use crate::trading::external::handler::buy_command_handler::{handle_buy_commands, Item};
+use crate::trading::external::handler::buy_command_handler::handle_buy_commands;
 use crate::trading::external::handler::mining_command_handler::handle_mining_commands;
 //use crate::trading::external::handler::mining_command_handler::handle_mining_command;
 use crate::trading::external::handler::movement_command_handler::handle_movement_commands;
+use crate::trading::external::handler::pickup_command_handler::handle_pickup_commands;
 use crate::trading::external::handler::regenerate_command_handler::handle_regenerate_commands;
 use crate::trading::external::handler::sell_command_handler::handle_selling_commands;
+use crate::trading::external::handler::standing_order_handler::expand_standing_orders;
 
 pub fn game_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(create_game)
@@ -43,14 +64,29 @@ pub fn game_routes(cfg: &mut web::ServiceConfig) {
         .service(delete_all_games)
         .service(get_players)
         .service(join_game)
+        .service(leave_game)
         .service(display_map)
         .service(display_map_for_round)
         .service(display_map_for_player)
         .service(display_map_for_round_and_player)
+        .service(get_path)
+        .service(export_map)
+        .service(get_map_presets)
+        .service(get_map_fairness)
+        .service(get_map_svg)
+        .service(get_map_png)
+        .service(get_replay_gif)
         .service(start_game)
         .service(end_game)
+        .service(get_game_results)
         .service(handle_batch_of_commands)
+        .service(get_queued_commands)
+        .service(cancel_queued_commands)
+        .service(mark_player_ready)
+        .service(set_standing_order)
+        .service(cancel_standing_order)
         .service(get_robots_for_current_round)
+        .service(get_moves_for_robot)
         .service(get_robot_for_current_round_by_player_id_and_robot_id)
         .service(get_player_state_for_current_round_with_xy_for_planets)
         .service(get_player_state_for_specified_round_with_xy_for_planets)
@@ -66,11 +102,14 @@ struct PlanetPlayerDto {
     movement_difficulty: u8,
     resource: Option<Resource>,
     resource_amount: u32,
+    loot: HashMap<Resource, u32>,
     amount_of_friendly_robots: u16,
     fighting_score_friendly_robots: f32,
     amount_of_enemy_robots: u16,
     fighting_score_enemy_robots: f32,
     neighbours: HashMap<Direction, Uuid>,
+    movement_costs: HashMap<Direction, u32>, // Energy needed to move to the neighbour in this direction
+    last_seen_round: u16, // Round in which the resources, loot and robots shown were seen, older than the current round with fog of war
 }
 
 #[derive(serde::Serialize, Clone)]
@@ -112,18 +151,39 @@ struct RobotDto {
 struct PlayerStateDto {
     current_round: u16,
     player_name: String,
+    status: PlayerStatus,
     money: u32,
     total_money_made: u32,
+    team: String,
+    teammates: Vec<String>,
     map: HashMap<Uuid, PlanetPlayerDto>,
     //(x,y) -> PlanetDto
-    visited_planets: HashSet<Uuid>,
+    visited_planets: HashSet<Uuid>, // Planets visited by the player or one of their teammates
     // PlanetId -> Planet
     alive_robots: HashMap<Uuid, RobotDto>,
+    allied_robots: HashMap<Uuid, RobotDto>, // Alive robots of teammates
     alive_enemy_robots: HashMap<Uuid, RobotDto>,
     // YourRobotId -> YOurRobot
     dead_robots: HashMap<Uuid, RobotDto>,
     // YOurRobotId -> YOurRobot
     killed_robots: HashMap<Uuid, Vec<(String, RobotDto)>>, // YOurRobotId -> (EnemyPlayerName, EnemyRobot)
+    assisted_kills: HashMap<Uuid, Vec<(String, RobotDto)>>, // YOurRobotId -> (EnemyPlayerName, EnemyRobot killed by another robot)
+    rejected_commands: Vec<CommandRejection>,
+    standing_orders: HashMap<Uuid, StandingOrder>, // YourRobotId -> Order which is still being carried out
+    enemy_intel: HashMap<Uuid, EnemyIntelDto>, // EnemyRobotId -> Last sighting of the enemy robot
+}
+
+#[derive(serde::Serialize, Clone)]
+struct EnemyIntelDto {
+    player_name: String,
+    x: usize,
+    y: usize,
+    planet_id: Uuid,
+    last_seen_round: u16,
+    rounds_since_seen: u16,
+    levels: RobotLevels,
+    health: u32,
+    max_health: u32,
 }
 
 #[derive(Serialize, Clone)]
@@ -133,10 +193,19 @@ pub struct RoundStateDto {
     pub map: HashMap<Uuid, PlanetPlayerDto>,
 }
 
-async fn with_game_lock<F, Fut>(redis_client: &web::Data<Pool<RedisConnectionManager>>, game_id: &String, action: F) -> Option<HttpResponse>
+pub async fn with_game_lock<F, Fut>(redis_client: &web::Data<Pool<RedisConnectionManager>>, game_id: &String, action: F) -> Option<HttpResponse>
     where
         F: FnOnce() -> Fut,
         Fut: Future<Output=Option<HttpResponse>>,
+{
+    with_lock(redis_client, game_id, action).await
+}
+
+/// Like `with_game_lock`, for background tasks whose action returns something else than a response.
+pub async fn with_lock<F, Fut, T>(redis_client: &web::Data<Pool<RedisConnectionManager>>, game_id: &String, action: F) -> Option<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output=Option<T>>,
 {
     let lock_key = format!("lock:game:{}", &game_id);
 
@@ -163,22 +232,65 @@ async fn with_game_lock<F, Fut>(redis_client: &web::Data<Pool<RedisConnectionMan
     response
 }
 
-#[derive(serde::Deserialize)]
-struct CreateGameRequestBody {
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct CreateGameRequestBody {
     max_rounds: u16,
     max_players: u8,
     map_size: u8,
+    #[serde(default)]
+    rules: GameRules,
+    seed: Option<u64>,
+    #[serde(default)]
+    topology: MapTopology,
+    #[serde(default)]
+    symmetry: MapSymmetry,
+    #[serde(default)]
+    resource_profile: ResourceProfile,
+    map: Option<MapFile>,
+    map_preset: Option<String>,
 }
 
-#[actix_web::post("/games")]
-async fn create_game(body: web::Json<CreateGameRequestBody>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
-    let game_id = Uuid::new_v4();
-    let new_game = GameState::new(
-        game_id,
+impl CreateGameRequestBody {
+    pub fn get_rules(&self) -> &GameRules {
+        &self.rules
+    }
+}
+
+/// Creates the map from the given map file or preset, or generates one of the given topology.
+fn create_map_for_game(body: &CreateGameRequestBody, seed: u64) -> Result<CreatedMap, MapError> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    match (&body.map, &body.map_preset) {
+        (Some(_), Some(_)) => Err(MapError::InvalidMap("Only one of map and map_preset can be given".to_string())),
+        (Some(map_file), None) => map_file.to_planets(&mut rng),
+        (None, Some(preset)) => MapFile::get_preset(preset)?.to_planets(&mut rng),
+        (None, None) => MapGenerator::create_map_with_topology(body.map_size as usize, &body.topology, &body.symmetry, &body.resource_profile, &mut rng),
+    }
+}
+
+/// Creates a new game which is played on the given seed.
+/// The map is created from the game's seed as well, so games with the same seed are played on the same map.
+pub fn new_game_state_from_request(body: &CreateGameRequestBody, seed: u64) -> Result<GameState, MapError> {
+    let (planets, spawn_points) = create_map_for_game(body, seed)?;
+    let mut new_game = GameState::new(
+        Uuid::new_v4(),
         body.max_rounds,
         body.max_players,
-        MapGenerator::create_map(body.map_size as usize),
+        planets,
+        body.rules.clone(),
+        seed,
     );
+    new_game.round_states.get_mut(&0).unwrap().map.spawn_points = spawn_points;
+    Ok(new_game)
+}
+
+#[actix_web::post("/games")]
+async fn create_game(body: web::Json<CreateGameRequestBody>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let seed = body.seed.unwrap_or_else(rand::random);
+    let new_game = match new_game_state_from_request(&body, seed) {
+        Ok(new_game) => new_game,
+        Err(error) => return HttpResponse::BadRequest().body(format!("Map could not be created: {error}")),
+    };
+    let game_id = new_game.game_id;
     //save game to redis
     let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
     let _: () = con.set(format!("games/{}", game_id.to_string()), serde_json::to_string(&new_game).unwrap()).await.expect("Failed to set key");
@@ -365,36 +477,36 @@ async fn get_players(path: web::Path<String>, redis_client: web::Data<Pool<Redis
     let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
     let game: String = con.get(format!("games/{}", &game_id)).await.expect(format!("Failed to get game {}", game_id).as_str());
     let game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+    let players = game_state.round_states.get(&game_state.current_round).map(|round_state| &round_state.player_name_player_map);
+    let player_statuses: HashMap<&String, &PlayerStatus> = players
+        .map(|players| players.iter().map(|(player_name, player)| (player_name, &player.status)).collect())
+        .unwrap_or_default();
+    let bots: HashMap<&String, &BotStrategy> = players
+        .map(|players| players.iter().filter_map(|(player_name, player)| Some((player_name, player.bot.as_ref()?))).collect())
+        .unwrap_or_default();
     return HttpResponse::Ok().insert_header(ContentType::json()).body(json!({
-        "participating_players": game_state.participating_players
+        "participating_players": game_state.participating_players,
+        "teams": game_state.teams,
+        "player_statuses": player_statuses,
+        "bots": bots,
     }).to_string());
 }
 
 #[derive(serde::Deserialize)]
 pub struct JoinGameRequestBody {
     player_name: String,
+    team: Option<String>,
+    bot: Option<BotStrategy>, // Joins a built-in bot, whose commands are queued by the server
 }
 
+pub const STARTING_MONEY: u32 = 500;
+
 #[actix_web::put("/games/{game_id}")]
 async fn join_game(body: web::Json<JoinGameRequestBody>, path: web::Path<String>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
     let game_id = path.into_inner();
-    let starting_money: u32 = 500;
-    let player = PlayerState {
-        player_name: body.player_name.to_string(),
-        money: Money { amount: starting_money },
-        total_money_made: Money { amount: starting_money },
-        visited_planets: HashSet::new(),
-        commands: vec![
-            (CommandType::SELLING, VecDeque::new()),
-            (CommandType::BUYING, VecDeque::new()),
-            (CommandType::MOVEMENT, VecDeque::new()),
-            (CommandType::BATTLE, VecDeque::new()),
-            (CommandType::MINING, VecDeque::new()),
-            (CommandType::REGENERATE, VecDeque::new()),
-        ].into_iter().collect(),
-        robots: HashMap::new(),
-        killed_robots: HashMap::new(),
-    };
+    let starting_money: u32 = STARTING_MONEY;
+    let mut player = PlayerState::new(body.player_name.to_string(), starting_money);
+    player.bot = body.bot.clone();
     with_game_lock(&redis_client, &game_id, || async {
         {
             let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
@@ -406,9 +518,10 @@ async fn join_game(body: web::Json<JoinGameRequestBody>, path: web::Path<String>
             if game_state.participating_players.contains(&body.player_name) {
                 return Some(HttpResponse::BadRequest().body(format!("Game {} can't be joined because player {} has already joined", &game_id, &body.player_name)));
             }
-            game_state.participating_players.push(player.player_name.clone());
-            let round_state = game_state.round_states.get_mut(&0).unwrap();
-            round_state.player_name_player_map.insert(body.player_name.to_string(), player);
+            if game_state.is_team_name_taken(&body.player_name, body.team.as_deref()) {
+                return Some(HttpResponse::BadRequest().body(format!("Game {} can't be joined because the team name {} is already the name of another player or team", &game_id, body.team.as_deref().unwrap_or(&body.player_name))));
+            }
+            game_state.add_player(player, body.team.clone());
             let is_write_successful: bool = con.set(format!("games/{}", &game_id), serde_json::to_string(&game_state).unwrap()).await.unwrap_or(false);
             if !is_write_successful {
                 return Some(HttpResponse::InternalServerError().body(format!("Failed to write game {} to Redis", &game_id)));
@@ -417,12 +530,57 @@ async fn join_game(body: web::Json<JoinGameRequestBody>, path: web::Path<String>
                 "player_name" : body.player_name,
                 "game_id": game_id,
                 "money": starting_money,
+                "team": game_state.get_team_of_player(&body.player_name),
+                "bot": body.bot,
             }).to_string()));
         }
     }).await.unwrap_or(HttpResponse::NotFound().body(format!("Game {game_id} can't be joined because it was not found.")))
 }
 
 
+#[actix_web::delete("/games/{game_id}/players/{player_name}")]
+async fn leave_game(path: web::Path<(String, String)>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let (game_id, player_name) = path.into_inner();
+    with_game_lock(&redis_client, &game_id, || async {
+        let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+        let game: String = con.get(format!("games/{}", &game_id)).await.unwrap_or(None)?;
+        let mut game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+        if !game_state.participating_players.contains(&player_name) {
+            return Some(HttpResponse::NotFound().body(format!("Player {} is not part of game {}", &player_name, &game_id)));
+        }
+        match game_state.status {
+            GameStatus::Created => {
+                game_state.remove_player(&player_name);
+                let is_write_successful: bool = con.set(format!("games/{}", &game_id), serde_json::to_string(&game_state).unwrap()).await.unwrap_or(false);
+                if !is_write_successful {
+                    return Some(HttpResponse::InternalServerError().body(format!("Failed to write game {} to Redis", &game_id)));
+                }
+                info!("Player {} left game {}", &player_name, &game_id);
+            }
+            GameStatus::Started => {
+                let status = game_state.get_player_for_current_round_as_mut(&player_name).map(|player| player.status.clone());
+                if status == Some(PlayerStatus::Forfeited) {
+                    return Some(HttpResponse::BadRequest().body(format!("Player {} has already forfeited game {}", &player_name, &game_id)));
+                }
+                game_state.forfeit_player(&player_name);
+                info!("Player {} forfeited game {}", &player_name, &game_id);
+                // The remaining players may have been waiting only for this player
+                if let Err(response) = process_round_if_all_players_submitted(game_state, &redis_client, &mut con, &game_id).await {
+                    return Some(response);
+                }
+            }
+            GameStatus::Ended => {
+                return Some(HttpResponse::BadRequest().body(format!("Game {} can't be left because it has already ended", &game_id)));
+            }
+        }
+        Some(HttpResponse::Ok().insert_header(ContentType::json()).body(json!({
+            "player_name": player_name,
+            "game_id": game_id,
+        }).to_string()))
+    }).await.unwrap_or(HttpResponse::NotFound().body(format!("Game {game_id} can't be left because it was not found.")))
+}
+
+
 #[actix_web::get("/games/{game_id}/map")]
 async fn display_map(path: web::Path<String>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
     let game_id = path.into_inner();
@@ -438,6 +596,116 @@ async fn display_map(path: web::Path<String>, redis_client: web::Data<Pool<Redis
     return HttpResponse::Ok().body(game_map_as_string);
 }
 
+#[derive(serde::Deserialize)]
+struct MapImageQuery {
+    round: Option<u16>, // The current round if omitted
+    player: Option<String>, // Draws the map as the player sees it
+}
+
+/// Loads the game and draws its map as SVG, see `render_svg`. Returns the response to send if that isn't possible.
+async fn render_map_for_query(redis_client: &Pool<RedisConnectionManager>, game_id: &str, query: &MapImageQuery) -> Result<String, HttpResponse> {
+    let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+    let game: Option<String> = con.get(format!("games/{}", game_id)).await.unwrap_or(None);
+    let Some(game) = game else {
+        return Err(HttpResponse::NotFound().body(format!("Game {game_id} was not found.")));
+    };
+    let game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+    let round_number = query.round.unwrap_or(game_state.current_round);
+    render_svg(&game_state, round_number, query.player.as_deref())
+        .ok_or_else(|| HttpResponse::NotFound().body(format!("Round {round_number} or player {:?} was not found in game {game_id}", query.player)))
+}
+
+#[actix_web::get("/games/{game_id}/map.svg")]
+async fn get_map_svg(path: web::Path<String>, query: web::Query<MapImageQuery>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    match render_map_for_query(&redis_client, &path.into_inner(), &query).await {
+        Ok(svg) => HttpResponse::Ok().content_type("image/svg+xml").body(svg),
+        Err(response) => response,
+    }
+}
+
+#[actix_web::get("/games/{game_id}/map.png")]
+async fn get_map_png(path: web::Path<String>, query: web::Query<MapImageQuery>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let svg = match render_map_for_query(&redis_client, &path.into_inner(), &query).await {
+        Ok(svg) => svg,
+        Err(response) => return response,
+    };
+    match web::block(move || render_png(&svg)).await {
+        Ok(Ok(png)) => HttpResponse::Ok().content_type("image/png").body(png),
+        Ok(Err(error)) => HttpResponse::InternalServerError().body(format!("Map could not be rendered: {error}")),
+        Err(error) => HttpResponse::InternalServerError().body(format!("Map could not be rendered: {error}")),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ReplayQuery {
+    player: Option<String>, // Draws the game as the player saw it
+    frame_ms: Option<u16>, // How long every round is shown, 500 milliseconds if omitted
+}
+
+/// Renders all rounds of the game as an animated GIF, see `render_replay_gif`.
+#[actix_web::get("/games/{game_id}/replay.gif")]
+async fn get_replay_gif(path: web::Path<String>, query: web::Query<ReplayQuery>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let game_id = path.into_inner();
+    let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+    let game: Option<String> = con.get(format!("games/{}", &game_id)).await.unwrap_or(None);
+    let Some(game) = game else {
+        return HttpResponse::NotFound().body(format!("Game {game_id} was not found."));
+    };
+    let game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+    let ReplayQuery { player, frame_ms } = query.into_inner();
+    if player.as_ref().is_some_and(|player| !game_state.participating_players.contains(player)) {
+        return HttpResponse::NotFound().body(format!("Player {} is not part of game {}", player.unwrap(), &game_id));
+    }
+    // Rendering every round takes a while, so it must not block the worker
+    match web::block(move || render_replay_gif(&game_state, player.as_deref(), frame_ms.unwrap_or(500))).await {
+        Ok(Ok(gif)) => HttpResponse::Ok().content_type("image/gif").body(gif),
+        Ok(Err(error)) => HttpResponse::InternalServerError().body(format!("Replay could not be rendered: {error}")),
+        Err(error) => HttpResponse::InternalServerError().body(format!("Replay could not be rendered: {error}")),
+    }
+}
+
+/// Exports the map as it was at the start of the game, so it can be used to create other games.
+#[actix_web::get("/games/{game_id}/map/export")]
+async fn export_map(path: web::Path<String>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let game_id = path.into_inner();
+    let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+    let game: Option<String> = con.get(format!("games/{}", &game_id)).await.unwrap_or(None);
+    let Some(game) = game else {
+        return HttpResponse::NotFound().body(format!("Game {game_id} was not found."));
+    };
+    let game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+    let first_round = game_state.round_states.keys().min().expect("Game has no rounds");
+    HttpResponse::Ok().json(MapFile::from_game_map(&game_state.round_states[first_round].map))
+}
+
+/// Compares the resources around the spawn points of the map, see `FairnessReport`.
+#[actix_web::get("/games/{game_id}/map/fairness")]
+async fn get_map_fairness(path: web::Path<String>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let game_id = path.into_inner();
+    let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+    let game: Option<String> = con.get(format!("games/{}", &game_id)).await.unwrap_or(None);
+    let Some(game) = game else {
+        return HttpResponse::NotFound().body(format!("Game {game_id} was not found."));
+    };
+    let game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+    let first_round = game_state.round_states.keys().min().expect("Game has no rounds");
+    let round_state = &game_state.round_states[first_round];
+    if round_state.map.spawn_points.is_empty() {
+        return HttpResponse::NotFound().body(format!("The map of game {game_id} has no spawn points."));
+    }
+    let starts = round_state.map.spawn_points.iter()
+        .map(|spawn_point| (*spawn_point, round_state.player_name_player_map.values()
+            .find(|player| player.home_planet == Some(*spawn_point))
+            .map(|player| player.player_name.clone())))
+        .collect();
+    HttpResponse::Ok().json(FairnessReport::new(&round_state.map, &game_state.rules.movement_cost, starts))
+}
+
+#[actix_web::get("/maps/presets")]
+async fn get_map_presets() -> impl Responder {
+    HttpResponse::Ok().json(MapFile::get_preset_names())
+}
+
 #[actix_web::get("/games/{game_id}/map/rounds/{round_number}")]
 async fn display_map_for_round(path: web::Path<(String, u16)>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
     let (game_id, round_number) = path.into_inner();
@@ -509,6 +777,34 @@ async fn display_map_for_round_and_player(path: web::Path<(String, u16, String)>
     return HttpResponse::Ok().body(game_map_as_string);
 }
 
+#[derive(serde::Deserialize)]
+struct PathQuery {
+    from: Uuid,
+    to: Uuid,
+    player: String,
+}
+
+#[actix_web::get("/games/{game_id}/path")]
+async fn get_path(path: web::Path<String>, query: web::Query<PathQuery>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let game_id = path.into_inner();
+    let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+    let game: Option<String> = con.get(format!("games/{}", &game_id)).await.unwrap_or(None);
+    let Some(game) = game else {
+        return HttpResponse::NotFound().body(format!("Game {game_id} was not found."));
+    };
+    let game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+    let current_round = game_state.current_round;
+    if game_state.get_player_for_round(&query.player, current_round).is_none() {
+        return HttpResponse::NotFound().body(format!("Player {} is not part of game {}", &query.player, &game_id));
+    }
+    let known_planets = game_state.get_known_planets(&query.player, current_round);
+    let map = &game_state.round_states[&current_round].map;
+    match find_cheapest_path(map, &game_state.rules.movement_cost, Some(&known_planets), query.from, query.to) {
+        Some(path) => HttpResponse::Ok().json(path),
+        None => HttpResponse::NotFound().body(format!("Player {} knows no route from planet {} to planet {}", &query.player, &query.from, &query.to)),
+    }
+}
+
 #[actix_web::post("/games/{game_id}/gameCommands/start")]
 async fn start_game(path: web::Path<String>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
     let game_id = path.into_inner();
@@ -523,7 +819,7 @@ async fn start_game(path: web::Path<String>, redis_client: web::Data<Pool<RedisC
             if game_state.participating_players.len() == 0 {
                 return Some(HttpResponse::BadRequest().body(format!("Game {} can't be started because no player has joined yet", &game_id)));
             }
-            game_state.status = GameStatus::Started;
+            game_state = start_game_state(game_state, &redis_client);
             let is_write_successful: bool = con.set(format!("games/{}", &game_id), serde_json::to_string(&game_state).unwrap()).await.unwrap_or(false);
             if !is_write_successful {
                 return Some(HttpResponse::InternalServerError().body(format!("Failed to write game {} to Redis", &game_id)));
@@ -536,6 +832,13 @@ async fn start_game(path: web::Path<String>, redis_client: web::Data<Pool<RedisC
     }).await.unwrap_or(HttpResponse::NotFound().body(format!("Game {game_id} can't be started because it was not found.")))
 }
 
+/// Starts the first round. A game of built-in bots only is played in the background, once the game was saved.
+pub fn start_game_state(mut game_state: GameState, redis_client: &web::Data<Pool<RedisConnectionManager>>) -> GameState {
+    game_state.status = GameStatus::Started;
+    start_round_timer(redis_client, &mut game_state);
+    game_state
+}
+
 #[actix_web::post("/games/{game_id}/gameCommands/end")]
 async fn end_game(path: web::Path<String>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
     let game_id = path.into_inner();
@@ -548,6 +851,7 @@ async fn end_game(path: web::Path<String>, redis_client: web::Data<Pool<RedisCon
                 return Some(HttpResponse::BadRequest().body(format!("Game {} can't be ended because it is currently in status {:?}", &game_id, &game_state.status)));
             }
             game_state.status = GameStatus::Ended;
+            game_state.results = Some(GameResults::from_game_state(&game_state));
             let is_write_successful: bool = con.set(format!("games/{}", &game_id), serde_json::to_string(&game_state).unwrap()).await.unwrap_or(false);
             if !is_write_successful {
                 return Some(HttpResponse::InternalServerError().body(format!("Failed to write game {} to Redis", &game_id)));
@@ -560,46 +864,86 @@ async fn end_game(path: web::Path<String>, redis_client: web::Data<Pool<RedisCon
     }).await.unwrap_or(HttpResponse::NotFound().body(format!("Game {game_id} can't be ended because it was not found.")))
 }
 
+#[actix_web::get("/games/{game_id}/results")]
+async fn get_game_results(path: web::Path<String>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let game_id = path.into_inner();
+    let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+    let game: Option<String> = con.get(format!("games/{}", &game_id)).await.unwrap_or(None);
+    let Some(game) = game else {
+        return HttpResponse::NotFound().body(format!("Game {game_id} was not found."));
+    };
+    let game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+    if game_state.status != GameStatus::Ended {
+        return HttpResponse::BadRequest().body(format!("Game {} has no results because it is currently in status {:?}", &game_id, &game_state.status));
+    }
+    let results = game_state.results.clone().unwrap_or_else(|| GameResults::from_game_state(&game_state));
+    HttpResponse::Ok().json(results)
+}
+
 fn all_players_submitted_commands(game_state: &GameState) -> bool {
     let current_round = game_state.current_round;
     let round_state = game_state.round_states.get(&current_round).unwrap();
     let players = &round_state.player_name_player_map;
-    players.values().all(|player| {
-        let player_has_commands = player.commands.values().any(|commands| !commands.is_empty());
-
-        let all_robots_dead = player.robots.par_iter().all(|(_, robot)| !robot.is_alive());
-
-        let player_with_only_dead_or_no_robots_has_buying_command_for_new_robots = all_robots_dead && player.commands.values().any(|commands| commands.iter().any(|command| command.command_type == CommandType::BUYING));
-
-        let mut alive_robot_ids_for_player = player.robots
-            .par_iter()
-            .filter_map(|(&robot_id, robot)| if robot.is_alive() { Some(robot_id) } else { None })
-            .collect::<HashSet<Uuid>>();
-        let alive_robot_amount = alive_robot_ids_for_player.len();
-
-        //When a player has no robots, he needs to submit atleast one Buying Robot Command.
-        //If he has robots, he needs to submit atleast one command for each alive robot that he owns.
-        //Commands for robots can be Buying (CommandType::Buying, but has a robot_id in the commandObject), Selling, Movement, Battle, Mining, Regenerate, so you have to check for all of them.
-        for (_, command_queue) in player.commands.iter() {
-            for command in command_queue.iter() {
-                if let Some(robot_id) = command.command_object.robot_id {
-                    //Remove robot_id from robot_ids_for_player
-                    alive_robot_ids_for_player.remove(&robot_id);
-                }
+    // Eliminated, disconnected and forfeited players don't take part in the round, so nobody waits for them.
+    // Built-in bots queue their commands when the round is processed, so nobody waits for them either.
+    // When batches are merged, a player also has to say that they are done.
+    let ready_required = game_state.rules.submission_mode == SubmissionMode::Merge;
+    players.values().filter(|player| player.status == PlayerStatus::Active && player.bot.is_none()).all(|player| (player.ended_turn || !ready_required) && match game_state.rules.turn_gate {
+        TurnGate::AllRobotsCovered => player_has_commands_for_all_robots(player),
+        TurnGate::OneBatchPerPlayer | TurnGate::Timeout { .. } => player.submitted_commands,
+        TurnGate::EndTurnMarker => player.ended_turn,
+    })
+}
+
+fn player_has_commands_for_all_robots(player: &PlayerState) -> bool {
+    // Robots with a standing order get their command when the round is processed
+    let player_has_commands = player.commands.values().any(|commands| !commands.is_empty())
+        || player.robots.values().any(|robot| robot.is_alive() && robot.standing_order.is_some());
+
+    let all_robots_dead = player.robots.par_iter().all(|(_, robot)| !robot.is_alive());
+
+    let player_with_only_dead_or_no_robots_has_buying_command_for_new_robots = all_robots_dead && player.commands.values().any(|commands| commands.iter().any(|command| command.command_type == CommandType::BUYING));
+
+    let mut alive_robot_ids_for_player = player.robots
+        .par_iter()
+        .filter_map(|(&robot_id, robot)| if robot.is_alive() && robot.standing_order.is_none() { Some(robot_id) } else { None })
+        .collect::<HashSet<Uuid>>();
+    let alive_robot_amount = player.robots.values().filter(|robot| robot.is_alive()).count();
+
+    //When a player has no robots, he needs to submit atleast one Buying Robot Command.
+    //If he has robots, he needs to submit atleast one command for each alive robot that he owns.
+    //Commands for robots can be Buying (CommandType::Buying, but has a robot_id in the commandObject), Selling, Movement, Battle, Mining, Regenerate, so you have to check for all of them.
+    for (_, command_queue) in player.commands.iter() {
+        for command in command_queue.iter() {
+            if let Some(robot_id) = command.command_object.robot_id {
+                //Remove robot_id from robot_ids_for_player
+                alive_robot_ids_for_player.remove(&robot_id);
             }
         }
-        let player_with_robots_has_commands_for_every_robot = alive_robot_ids_for_player.is_empty() && alive_robot_amount > 0;
+    }
+    let player_with_robots_has_commands_for_every_robot = alive_robot_ids_for_player.is_empty() && alive_robot_amount > 0;
 
-        debug!("Player {} has commands: {}, player_with_only_dead_or_no_robots_has_buying_command_for_new_robots: {}, player_with_robots_has_commands_for_every_robot: {} Number left in set: {}", player.player_name, player_has_commands, player_with_only_dead_or_no_robots_has_buying_command_for_new_robots, player_with_robots_has_commands_for_every_robot, alive_robot_ids_for_player.len());
+    debug!("Player {} has commands: {}, player_with_only_dead_or_no_robots_has_buying_command_for_new_robots: {}, player_with_robots_has_commands_for_every_robot: {} Number left in set: {}", player.player_name, player_has_commands, player_with_only_dead_or_no_robots_has_buying_command_for_new_robots, player_with_robots_has_commands_for_every_robot, alive_robot_ids_for_player.len());
 
-        player_has_commands && (player_with_only_dead_or_no_robots_has_buying_command_for_new_robots || player_with_robots_has_commands_for_every_robot)
-    })
+    player_has_commands && (player_with_only_dead_or_no_robots_has_buying_command_for_new_robots || player_with_robots_has_commands_for_every_robot)
 }
 
 async fn process_commands_for_current_round(mut game_state: GameState, should_spawn_robots: bool, hypothetically: bool) -> Option<GameState> {
     let current_round = game_state.current_round;
+    if !hypothetically {
+        queue_bot_commands(&mut game_state);
+    }
     let old_round_state = game_state.round_states.get(&current_round).unwrap().clone();
 
+    for player in game_state.round_states.get_mut(&current_round).unwrap().player_name_player_map.values_mut() {
+        player.rejected_commands.clear();
+        player.submitted_commands = false;
+        player.ended_turn = false;
+        if player.status != PlayerStatus::Active {
+            player.commands.values_mut().for_each(VecDeque::clear);
+        }
+    }
+    expand_standing_orders(&mut game_state);
     handle_selling_commands(&mut game_state);
     handle_buy_commands(&mut game_state, should_spawn_robots);
     handle_movement_commands(&mut game_state);
@@ -607,8 +951,11 @@ async fn process_commands_for_current_round(mut game_state: GameState, should_sp
     let damage_reports = calculate_damage_for_round(&mut game_state).await;
     apply_damage_for_round(damage_reports, &mut game_state);
     delete_commands_for_dead_robots(&mut game_state);
+    handle_pickup_commands(&mut game_state);
     handle_mining_commands(&mut game_state);
     handle_regenerate_commands(&mut game_state);
+    game_state.update_planet_memories();
+    game_state.update_enemy_intel();
 
     if hypothetically {
         return Some(game_state);
@@ -623,6 +970,100 @@ async fn process_commands_for_current_round(mut game_state: GameState, should_sp
     Some(game_state)
 }
 
+/// Remembers when the current round started and processes the round in the background once nobody has to be waited for:
+/// right away if only built-in bots are playing, or once the time is up if the game uses `TurnGate::Timeout`.
+/// Bots play one round per task, so the game lock is only held for a single round and no request waits for them.
+fn start_round_timer(redis_client: &web::Data<Pool<RedisConnectionManager>>, game_state: &mut GameState) {
+    if game_state.status != GameStatus::Started {
+        return;
+    }
+    game_state.round_started_at = Some(SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_millis() as u64).unwrap_or(0));
+    let is_round_of_bots = only_bots_are_playing(game_state);
+    let delay = match game_state.rules.turn_gate {
+        _ if is_round_of_bots => Duration::ZERO,
+        TurnGate::Timeout { seconds } => Duration::from_secs(seconds),
+        _ => return,
+    };
+    let redis_client = redis_client.clone();
+    let game_id = game_state.game_id.to_string();
+    let round_number = game_state.current_round;
+    actix_web::rt::spawn(async move {
+        sleep(delay).await;
+        with_game_lock(&redis_client, &game_id, || async {
+            let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+            let game: String = con.get(format!("games/{}", &game_id)).await.unwrap_or(None)?;
+            let mut game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+            // The round was already processed, because every player submitted their commands in time
+            if game_state.status != GameStatus::Started || game_state.current_round != round_number {
+                return None;
+            }
+            if !is_round_of_bots {
+                end_round_after_timeout(&mut game_state);
+            }
+            process_round_if_all_players_submitted(game_state, &redis_client, &mut con, &game_id).await.err()
+        }).await;
+    });
+}
+
+/// Disconnects the players who didn't submit any commands in time. Players who submitted commands, but didn't say
+/// that they are done with merged batches, are taken as ready, so that the round isn't held up by them.
+fn end_round_after_timeout(game_state: &mut GameState) {
+    let game_id = game_state.game_id;
+    let round_number = game_state.current_round;
+    for player in game_state.round_states.get_mut(&round_number).unwrap().player_name_player_map.values_mut() {
+        if player.status != PlayerStatus::Active || player.bot.is_some() {
+            continue;
+        }
+        if player.submitted_commands {
+            player.ended_turn = true;
+        } else {
+            info!("Player {} did not submit commands for round {} of game {} in time", &player.player_name, round_number, &game_id);
+            player.status = PlayerStatus::Disconnected;
+        }
+    }
+}
+
+/// Saves the game and, once every active player submitted their commands, processes the round first.
+/// If only built-in bots are left to play afterwards, they go on with the next round in the background.
+/// Returns whether the round was processed.
+async fn process_round_if_all_players_submitted(game_state: GameState, redis_client: &web::Data<Pool<RedisConnectionManager>>, con: &mut Connection<RedisConnectionManager>, game_id: &str) -> Result<bool, HttpResponse> {
+    if !all_players_submitted_commands(&game_state) {
+        let is_write_successful: bool = con.set(format!("games/{}", game_id), serde_json::to_string(&game_state).unwrap()).await.unwrap_or(false);
+        if !is_write_successful {
+            return Err(HttpResponse::InternalServerError().body(format!("Failed to save game {} to Redis", game_id)));
+        }
+        return Ok(false);
+    }
+    let mut game_state = process_commands_for_current_round(game_state, true, false).await.unwrap();
+    game_state.end_game_if_decided();
+    start_round_timer(redis_client, &mut game_state);
+    let is_write_successful: bool = con.set(format!("games/{}", game_id), serde_json::to_string(&game_state).unwrap()).await.unwrap_or(false);
+    if !is_write_successful {
+        return Err(HttpResponse::InternalServerError().body(format!("Failed to save game {} to Redis", game_id)));
+    }
+    //Overwrite Hypothetical Game State with new round state (Saves time because Entire Gamestate with all rounds doesnt need to be deserialzied later on)
+    let current_round_state = game_state.round_states.remove(&game_state.current_round).unwrap();
+    game_state.round_states.clear();
+    game_state.round_states.insert(game_state.current_round, current_round_state);
+    let game_state_as_string = serde_json::to_string(&game_state).unwrap();
+    for player_name in game_state.participating_players {
+        let hypothetical_game_state_key = format!("hypogames/{}/players/{}/hypothetical_game_state", game_id, &player_name);
+        info!("Overwriting hypothetical state of game {} for player {player_name} with new round", game_id);
+        let _: () = con.set(&hypothetical_game_state_key, &game_state_as_string).await.unwrap_or(());
+    }
+    Ok(true)
+}
+
+/// Whether every player who can still take part in the game is a built-in bot, so there is nobody to wait for.
+/// Disconnected players count as players, because they may come back.
+fn only_bots_are_playing(game_state: &GameState) -> bool {
+    let Some(round_state) = game_state.round_states.get(&game_state.current_round) else { return false };
+    let mut players = round_state.player_name_player_map.values()
+        .filter(|player| player.status == PlayerStatus::Active || player.status == PlayerStatus::Disconnected)
+        .peekable();
+    players.peek().is_some() && players.all(|player| player.bot.is_some())
+}
+
 #[actix_web::post("/games/{game_id}/commands")]
 async fn handle_batch_of_commands(mut body: web::Json<Vec<Command>>, path: web::Path<String>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
     /*
@@ -630,9 +1071,10 @@ async fn handle_batch_of_commands(mut body: web::Json<Vec<Command>>, path: web::
     1. Trading
     2. Moving
     3. Repairing (Buying a health or energy restore)
-    4. Battleing (only possible when on same planet)
-    5. Mining
-    6. Regenerating
+    4. Battleing (only possible when the target is in attack range, see AttackRange)
+    5. Picking up loot
+    6. Mining
+    7. Regenerating
      */
     if body.0.is_empty() {
         return HttpResponse::BadRequest().body("No commands found");
@@ -646,76 +1088,223 @@ async fn handle_batch_of_commands(mut body: web::Json<Vec<Command>>, path: web::
         let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
         let game: String = con.get(format!("games/{}", &game_id)).await.expect(format!("Failed to get game {}", game_id).as_str());
         let mut game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
-        if game_state.status != GameStatus::Started {
-            return Some(HttpResponse::BadRequest().body(format!("Game {} can't take commands because it is currently in status {:?}", &game_id, &game_state.status)));
-        }
-        let current_round = game_state.current_round;
-        let round_state = game_state.round_states.get_mut(&current_round).unwrap();
-        let player = round_state.player_name_player_map.get_mut(&player_name).unwrap();
-        if player.commands.values().any(|commands| !commands.is_empty()) {
+        let submission_mode = game_state.rules.submission_mode.clone();
+        let player = match get_player_able_to_submit_commands(&mut game_state, &player_name, &game_id) {
+            Ok(player) => player,
+            Err(error) => return Some(error.error_response()),
+        };
+        if submission_mode == SubmissionMode::Replace && player.commands.values().any(|commands| !commands.is_empty()) {
             error!("Overwriting commands for player {}, before : {:?} ", player.player_name, player.commands);
             player.commands.clear();
         }
+        player.submitted_commands = true;
+        // Commands of robots in the new batch replace the ones queued before, this only matters when merging batches
+        let robot_ids_in_batch: HashSet<Uuid> = commands.iter().filter_map(|command| command.command_object.robot_id).collect();
+        remove_queued_commands(player, |command| command.command_object.robot_id.is_some_and(|robot_id| robot_ids_in_batch.contains(&robot_id)));
         for command in commands {
+            if command.command_type == CommandType::END_TURN {
+                player.ended_turn = true;
+                continue;
+            }
             player.commands.entry(command.command_type)
                 .or_insert_with(VecDeque::new)
                 .push_back(command);
         }
         debug!("Player {} submitted commands: {:?}", player.player_name, player.commands);
 
-        if all_players_submitted_commands(&game_state) {
-            let previous_round_number = game_state.current_round;
-            let mut game_state = process_commands_for_current_round(game_state, true, false).await.unwrap();
-            // Zähle die Anzahl der Spieler, die sich keine Roboter leisten können
-            let mut cannot_afford_robot_count = 0;
-            let player_count = game_state.round_states.get(&game_state.current_round).unwrap().player_name_player_map.len();
-
-            for player in game_state.round_states.get(&game_state.current_round).unwrap().player_name_player_map.values() {
-                // Wenn der Spieler keine ALIVE Robots mehr hat und kein Geld sich neue zu kaufen, dann kann er sich keine Roboter leisten
-                let alive_robots_exist = player.robots.iter().any(|(robot_id, robot)| robot.is_alive());
-                if !alive_robots_exist && player.money.amount < Item::Robot(1).get_cost() {
-                    cannot_afford_robot_count += 1;
-                    debug!("Player {} has no robots and can't afford to buy a new robot. HE LOST!", player.player_name);
-                }
-            }
-            let current_highest_round = game_state.current_round;
+        match process_round_if_all_players_submitted(game_state, &redis_client, &mut con, &game_id).await {
+            Ok(true) => Some(HttpResponse::Ok().finish()),
+            Ok(false) => Some(HttpResponse::Accepted().body("Waiting for other players to submit commands")),
+            Err(response) => Some(response),
+        }
+    })
+        .await
+        .unwrap_or(HttpResponse::NotFound().body(format!("Game {game_id} can't take commands because it was not found.")))
+}
 
-            // Setze current_round auf max_rounds, wenn alle bis auf maximal einen Spieler sich keine Roboter leisten können
-            if cannot_afford_robot_count >= player_count - 1 && player_count > 1 {
-                game_state.current_round = game_state.max_rounds;
-            }
-            if (game_state.current_round == game_state.max_rounds) {
-                game_state.status = GameStatus::Ended;
-                if !game_state.round_states.contains_key(&game_state.current_round) {
-                    let round_state = game_state.round_states.get(&current_highest_round).unwrap();
-                    game_state.round_states.insert(game_state.current_round, round_state.clone());
-                }
-            }
-            let is_write_successful: bool = con.set(format!("games/{}", &game_id), serde_json::to_string(&game_state).unwrap()).await.unwrap_or(false);
-            if !is_write_successful {
-                return Some(HttpResponse::InternalServerError().body(format!("Failed to save game {} to Redis", &game_id)));
-            }
-            //Overwrite Hypothetical Game State with new round state (Saves time because Entire Gamestate with all rounds doesnt need to be deserialzied later on)
-            let current_round_state = game_state.round_states.remove(&game_state.current_round).unwrap();
-            game_state.round_states.clear();
-            game_state.round_states.insert(game_state.current_round, current_round_state);
-            let game_state_as_string = serde_json::to_string(&game_state).unwrap();
-            for player_name in game_state.participating_players {
-                let hypothetical_game_state_key = format!("hypogames/{}/players/{}/hypothetical_game_state", &game_id, &player_name);
-                info!("Overwriting hypothetical state of game {} for player {player_name} with new round", &game_id);
-                let _: () = con.set(&hypothetical_game_state_key, &game_state_as_string).await.unwrap_or(());
-            }
-            return Some(HttpResponse::Ok().finish());
+/// Returns the player, if they may submit commands in the current round. Disconnected players become active again.
+fn get_player_able_to_submit_commands<'a>(game_state: &'a mut GameState, player_name: &str, game_id: &str) -> Result<&'a mut PlayerState, actix_web::Error> {
+    if game_state.status != GameStatus::Started {
+        return Err(ErrorBadRequest(format!("Game {} can't take commands because it is currently in status {:?}", game_id, &game_state.status)));
+    }
+    let Some(player) = game_state.get_player_for_current_round_as_mut(player_name) else {
+        return Err(ErrorNotFound(format!("Player {} is not part of game {}", player_name, game_id)));
+    };
+    if player.bot.is_some() {
+        return Err(ErrorBadRequest(format!("Player {} is a built-in bot, its commands are queued by the server", player_name)));
+    }
+    match player.status {
+        PlayerStatus::Eliminated | PlayerStatus::Forfeited => {
+            return Err(ErrorBadRequest(format!("Player {} can't submit commands because they are {:?}, they can only watch the game", player_name, &player.status)));
+        }
+        PlayerStatus::Disconnected => {
+            info!("Player {} reconnected to game {}", player_name, game_id);
+            player.status = PlayerStatus::Active;
         }
+        PlayerStatus::Active => {}
+    }
+    Ok(player)
+}
+
+fn remove_queued_commands(player: &mut PlayerState, should_remove: impl Fn(&Command) -> bool) {
+    for commands in player.commands.values_mut() {
+        commands.retain(|command| !should_remove(command));
+    }
+}
 
+#[actix_web::get("/games/{game_id}/currentRound/players/{player_name}/commands")]
+async fn get_queued_commands(path: web::Path<(String, String)>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let (game_id, player_name) = path.into_inner();
+    let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+    let game: Option<String> = con.get(format!("games/{}", &game_id)).await.unwrap_or(None);
+    let Some(game) = game else {
+        return HttpResponse::NotFound().body(format!("Game {game_id} was not found."));
+    };
+    let game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+    let Some(player) = game_state.get_player_for_round(&player_name, game_state.current_round) else {
+        return HttpResponse::NotFound().body(format!("Player {} is not part of game {}", &player_name, &game_id));
+    };
+    let queued_commands: Vec<&Command> = player.commands.values().flatten().collect();
+    HttpResponse::Ok().json(json!({
+        "commands": queued_commands,
+        "ready": player.ended_turn,
+    }))
+}
+
+#[derive(serde::Deserialize)]
+struct CancelCommandsQuery {
+    robot_id: Option<Uuid>,
+}
+
+#[actix_web::delete("/games/{game_id}/currentRound/players/{player_name}/commands")]
+async fn cancel_queued_commands(path: web::Path<(String, String)>, query: web::Query<CancelCommandsQuery>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let (game_id, player_name) = path.into_inner();
+    with_game_lock(&redis_client, &game_id, || async {
+        let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+        let game: String = con.get(format!("games/{}", &game_id)).await.unwrap_or(None)?;
+        let mut game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+        let player = match get_player_able_to_submit_commands(&mut game_state, &player_name, &game_id) {
+            Ok(player) => player,
+            Err(error) => return Some(error.error_response()),
+        };
+        match query.robot_id {
+            Some(robot_id) => remove_queued_commands(player, |command| command.command_object.robot_id == Some(robot_id)),
+            None => remove_queued_commands(player, |_| true),
+        }
+        // The player changed their mind, so they are not done with the round anymore
+        player.ended_turn = false;
         let is_write_successful: bool = con.set(format!("games/{}", &game_id), serde_json::to_string(&game_state).unwrap()).await.unwrap_or(false);
         if !is_write_successful {
             return Some(HttpResponse::InternalServerError().body(format!("Failed to save game {} to Redis", &game_id)));
         }
-        return Some(HttpResponse::Accepted().body("Waiting for other players to submit commands"));
-    })
-        .await
-        .unwrap_or(HttpResponse::NotFound().body(format!("Game {game_id} can't take commands because it was not found.")))
+        Some(HttpResponse::Ok().finish())
+    }).await.unwrap_or(HttpResponse::NotFound().body(format!("Game {game_id} was not found.")))
+}
+
+#[actix_web::post("/games/{game_id}/currentRound/players/{player_name}/ready")]
+async fn mark_player_ready(path: web::Path<(String, String)>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let (game_id, player_name) = path.into_inner();
+    with_game_lock(&redis_client, &game_id, || async {
+        let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+        let game: String = con.get(format!("games/{}", &game_id)).await.unwrap_or(None)?;
+        let mut game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+        let player = match get_player_able_to_submit_commands(&mut game_state, &player_name, &game_id) {
+            Ok(player) => player,
+            Err(error) => return Some(error.error_response()),
+        };
+        player.submitted_commands = true;
+        player.ended_turn = true;
+        match process_round_if_all_players_submitted(game_state, &redis_client, &mut con, &game_id).await {
+            Ok(true) => Some(HttpResponse::Ok().finish()),
+            Ok(false) => Some(HttpResponse::Accepted().body("Waiting for other players to submit commands")),
+            Err(response) => Some(response),
+        }
+    }).await.unwrap_or(HttpResponse::NotFound().body(format!("Game {game_id} was not found.")))
+}
+
+#[actix_web::put("/games/{game_id}/currentRound/players/{player_name}/robots/{robot_id}/order")]
+async fn set_standing_order(path: web::Path<(String, String, Uuid)>, body: web::Json<StandingOrder>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let (game_id, player_name, robot_id) = path.into_inner();
+    let standing_order = body.into_inner();
+    with_game_lock(&redis_client, &game_id, || async {
+        let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+        let game: String = con.get(format!("games/{}", &game_id)).await.unwrap_or(None)?;
+        let mut game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+        let player = match get_player_able_to_submit_commands(&mut game_state, &player_name, &game_id) {
+            Ok(player) => player,
+            Err(error) => return Some(error.error_response()),
+        };
+        let Some(robot) = player.robots.get_mut(&robot_id).filter(|robot| robot.is_alive()) else {
+            return Some(HttpResponse::NotFound().body(format!("Player {} has no alive robot {}", &player_name, &robot_id)));
+        };
+        info!("Robot {} of player {} got the standing order {:?}", &robot_id, &player_name, &standing_order);
+        robot.standing_order = Some(standing_order);
+        // The order may have been the last thing the other players were waiting for
+        match process_round_if_all_players_submitted(game_state, &redis_client, &mut con, &game_id).await {
+            Ok(true) => Some(HttpResponse::Ok().finish()),
+            Ok(false) => Some(HttpResponse::Accepted().body("Waiting for other players to submit commands")),
+            Err(response) => Some(response),
+        }
+    }).await.unwrap_or(HttpResponse::NotFound().body(format!("Game {game_id} was not found.")))
+}
+
+#[actix_web::delete("/games/{game_id}/currentRound/players/{player_name}/robots/{robot_id}/order")]
+async fn cancel_standing_order(path: web::Path<(String, String, Uuid)>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let (game_id, player_name, robot_id) = path.into_inner();
+    with_game_lock(&redis_client, &game_id, || async {
+        let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+        let game: String = con.get(format!("games/{}", &game_id)).await.unwrap_or(None)?;
+        let mut game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+        let Some(robot) = game_state.get_robots_for_current_round(&player_name).and_then(|robots| robots.get_mut(&robot_id)) else {
+            return Some(HttpResponse::NotFound().body(format!("Player {} has no robot {}", &player_name, &robot_id)));
+        };
+        robot.standing_order = None;
+        let is_write_successful: bool = con.set(format!("games/{}", &game_id), serde_json::to_string(&game_state).unwrap()).await.unwrap_or(false);
+        if !is_write_successful {
+            return Some(HttpResponse::InternalServerError().body(format!("Failed to save game {} to Redis", &game_id)));
+        }
+        Some(HttpResponse::Ok().finish())
+    }).await.unwrap_or(HttpResponse::NotFound().body(format!("Game {game_id} was not found.")))
+}
+
+fn get_movement_costs(planet: &Planet, map: &GameMap, movement_cost_model: &MovementCostModel) -> HashMap<Direction, u32> {
+    planet.neighbours.iter()
+        .filter_map(|(direction, neighbour_id)| map.get_planet(neighbour_id).map(|neighbour| (direction.clone(), movement_cost_model.get_movement_cost(planet, neighbour))))
+        .collect()
+}
+
+#[derive(serde::Serialize)]
+struct MoveDto {
+    direction: Direction,
+    planet_id: Uuid,
+    energy_cost: u32,
+    affordable: bool, // Whether the robot has enough energy for this move right now
+}
+
+#[actix_web::get("/games/{game_id}/currentRound/players/{player_name}/robots/{robot_id}/moves")]
+async fn get_moves_for_robot(path: web::Path<(String, String, Uuid)>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let (game_id, player_name, robot_id) = path.into_inner();
+    let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+    let game: Option<String> = con.get(format!("games/{}", &game_id)).await.unwrap_or(None);
+    let Some(game) = game else {
+        return HttpResponse::NotFound().body(format!("Game {game_id} was not found."));
+    };
+    let game_state: GameState = serde_json::from_str(game.as_str()).unwrap();
+    let Some(robot) = game_state.get_player_for_round(&player_name, game_state.current_round).and_then(|player| player.robots.get(&robot_id)) else {
+        return HttpResponse::NotFound().body(format!("Player {} has no robot {}", &player_name, &robot_id));
+    };
+    let map = &game_state.round_states[&game_state.current_round].map;
+    let planet = map.get_planet(&robot.planet_id).expect("Planet not found");
+    let mut moves: Vec<MoveDto> = get_movement_costs(planet, map, &game_state.rules.movement_cost).into_iter()
+        .map(|(direction, energy_cost)| MoveDto {
+            planet_id: planet.neighbours[&direction],
+            direction,
+            energy_cost,
+            affordable: robot.is_alive() && robot.energy >= energy_cost,
+        })
+        .collect();
+    moves.sort_by_key(|possible_move| possible_move.energy_cost);
+    HttpResponse::Ok().json(moves)
 }
 
 #[actix_web::get("/games/{game_id}/currentRound/players/{player_name}/robots")]
@@ -749,45 +1338,89 @@ async fn get_player_state_for_current_round(path: web::Path<(String, String)>, r
             let game_state: GameState = serde_json::from_str(&game).unwrap();
             let player_state = game_state.get_player_for_round(&player_name, game_state.current_round).unwrap();
             let enemy_robots = game_state.get_enemy_robots_for_current_round(&player_name).unwrap_or_else(|| Vec::new());
+            let allied_robots = game_state.get_allied_robots_for_current_round(&player_name).unwrap_or_default();
+            let visible_planets = game_state.get_visible_planets(&player_name, game_state.current_round);
+            let is_visible = |planet_id: &Uuid| visible_planets.as_ref().is_none_or(|visible_planets| visible_planets.contains(planet_id));
 
             #[derive(serde::Serialize)]
             struct PlayerStateDto<'a> {
                 current_round: u16,
                 player_name: String,
+                status: &'a PlayerStatus,
+                team: &'a str,
                 money: u32,
                 total_money_made: u32,
-                visited_planets: HashMap<String, &'a Planet>,
+                visited_planets: HashMap<String, Planet>, // With fog of war, planets out of sight are shown as they were last seen
                 // PlanetId -> Planet
                 alive_robots: HashMap<String, &'a Robot>,
+                allied_robots: Vec<&'a Robot>,
                 alive_enemy_robots: Vec<&'a Robot>,
                 // YourRobotId -> YOurRobot
                 dead_robots: HashMap<String, &'a Robot>,
                 // YOurRobotId -> YOurRobot
                 killed_robots: &'a HashMap<String, Vec<(String, Robot)>>, // YOurRobotId -> (EnemyPlayerName, EnemyRobot)
+                assisted_kills: &'a HashMap<String, Vec<(String, Robot)>>, // YOurRobotId -> (EnemyPlayerName, EnemyRobot killed by another robot)
+                rejected_commands: &'a Vec<CommandRejection>,
+                enemy_intel: HashMap<Uuid, EnemyIntelDto>,
             }
 
             let player_state_dto = PlayerStateDto {
                 current_round: game_state.current_round,
                 player_name: player_state.player_name.clone(),
+                status: &player_state.status,
+                team: game_state.get_team_of_player(&player_name),
                 money: player_state.money.amount,
                 total_money_made: player_state.total_money_made.amount,
                 visited_planets: player_state.visited_planets.iter().map(|planet_id| {
                     let (x, y) = game_state.round_states[&game_state.current_round].map.indices.get(planet_id).expect("Planet not found in indices");
-                    let planet = game_state.round_states[&game_state.current_round].map.planets[*x][*y].as_ref().unwrap();
+                    let mut planet = game_state.round_states[&game_state.current_round].map.planets[*x][*y].clone().unwrap();
+                    if let Some(planet_memory) = player_state.planet_memories.get(planet_id).filter(|_| !is_visible(planet_id)) {
+                        planet.resources = planet_memory.resources.clone();
+                        planet.loot = planet_memory.loot.clone();
+                    }
                     (planet_id.to_string(), planet) // Convert Uuid to String here
                 }).collect(),
                 alive_robots: player_state.robots.iter().filter(|(_, robot)| robot.is_alive()).map(|(&robot_id, robot)| (robot_id.to_string(), robot)).collect(),
-                alive_enemy_robots: enemy_robots.iter().filter(|robot| robot.is_alive()).map(|robot| *robot).collect(),
+                allied_robots: allied_robots.into_iter().filter(|robot| robot.is_alive()).collect(),
+                alive_enemy_robots: enemy_robots.iter().filter(|robot| robot.is_alive() && is_visible(&robot.planet_id)).copied().collect(),
                 dead_robots: player_state.robots.iter().filter(|(_, robot)| !robot.is_alive()).map(|(&robot_id, robot)| (robot_id.to_string(), robot)).collect(),
                 killed_robots: &player_state.killed_robots.iter().map(|(robot_id, killed_robots)| {
                     (robot_id.to_string(), killed_robots.iter().map(|(enemy_player_name, enemy_robot)| (enemy_player_name.clone(), enemy_robot.clone())).collect())
                 }).collect(),
+                assisted_kills: &player_state.assisted_kills.iter().map(|(robot_id, assisted_kills)| {
+                    (robot_id.to_string(), assisted_kills.iter().map(|(enemy_player_name, enemy_robot)| (enemy_player_name.clone(), enemy_robot.clone())).collect())
+                }).collect(),
+                rejected_commands: &player_state.rejected_commands,
+                enemy_intel: get_enemy_intel_dtos(player_state, game_state.current_round, &game_state.round_states[&game_state.current_round].map),
             };
             Some(HttpResponse::Ok().json(player_state_dto))
         }
     }).await.unwrap_or(HttpResponse::InternalServerError().body(format!("Game {game_id} playerstate cant be retrieved")))
 }
 
+fn get_robot_dto(robot: &Robot, map: &GameMap) -> RobotDto {
+    let (x, y) = map.indices.get(&robot.planet_id).expect("Planet not found in indices");
+    RobotDto {
+        x: *x,
+        y: *y,
+        robot_id: robot.robot_id,
+        planet_id: robot.planet_id,
+        health: robot.health,
+        max_health: robot.levels.get_health_for_level(),
+        energy: robot.energy,
+        max_energy: robot.levels.get_energy_for_level(),
+        energy_regen: robot.levels.get_energy_regen_for_level(),
+        storage: robot.get_used_storage_space(),
+        max_storage: robot.levels.get_storage_for_level(),
+        mining_speed: robot.levels.get_mining_speed_for_level(),
+        mineable_resources: robot.get_mineable_resources(),
+        damage: robot.levels.get_damage_for_level(),
+        fighting_score: robot.get_fighting_score(),
+        money_value: robot.get_money_costs_for_robots_existing_upgrades(),
+        money_made: robot.money_made,
+    }
+}
+
 fn get_player_state_dto_from_gamestate(game_state: GameState, player_name: &str, round_number: u16) -> PlayerStateDto {
     let map = &game_state.round_states[&game_state.current_round].map;
 
@@ -796,26 +1429,7 @@ fn get_player_state_dto_from_gamestate(game_state: GameState, player_name: &str,
     let (alive_robots, dead_robots) = player_state.robots.iter().fold(
         (HashMap::new(), HashMap::new()),
         |(mut alive, mut dead), (robot_id, robot)| {
-            let (x, y) = map.indices.get(&robot.planet_id).expect("Planet not found in indices");
-            let robot_dto = RobotDto {
-                x: *x,
-                y: *y,
-                robot_id: robot.robot_id,
-                planet_id: robot.planet_id,
-                health: robot.health,
-                max_health: robot.levels.get_health_for_level(),
-                energy: robot.energy,
-                max_energy: robot.levels.get_energy_for_level(),
-                energy_regen: robot.levels.get_energy_regen_for_level(),
-                storage: robot.get_used_storage_space(),
-                max_storage: robot.levels.get_storage_for_level(),
-                mining_speed: robot.levels.get_mining_speed_for_level(),
-                mineable_resources: robot.get_mineable_resources(),
-                damage: robot.levels.get_damage_for_level(),
-                fighting_score: robot.get_fighting_score(),
-                money_value: robot.get_money_costs_for_robots_existing_upgrades(),
-                money_made: robot.money_made,
-            };
+            let robot_dto = get_robot_dto(robot, map);
             if robot.health > 0 {
                 alive.insert(robot.robot_id, robot_dto);
             } else {
@@ -825,29 +1439,15 @@ fn get_player_state_dto_from_gamestate(game_state: GameState, player_name: &str,
         },
     );
 
-    let (alive_enemy_robots, dead_enemy_robots) = game_state.get_enemy_robots_for_current_round(&player_name).unwrap_or_else(|| Vec::new()).iter().fold(
+    // None if the player can see the whole map
+    let visible_planets = game_state.get_visible_planets(player_name, round_number);
+    let is_visible = |planet_id: &Uuid| visible_planets.as_ref().is_none_or(|visible_planets| visible_planets.contains(planet_id));
+    let (alive_enemy_robots, _dead_enemy_robots) = game_state.get_enemy_robots_for_current_round(player_name).unwrap_or_default().iter()
+        .filter(|robot| is_visible(&robot.planet_id))
+        .fold(
         (HashMap::new(), HashMap::new()),
         |(mut alive, mut dead), robot| {
-            let (x, y) = map.indices.get(&robot.planet_id).expect("Planet not found in indices");
-            let robot_dto = RobotDto {
-                x: *x,
-                y: *y,
-                robot_id: robot.robot_id,
-                planet_id: robot.planet_id,
-                health: robot.health,
-                max_health: robot.levels.get_health_for_level(),
-                energy: robot.energy,
-                max_energy: robot.levels.get_energy_for_level(),
-                energy_regen: robot.levels.get_energy_regen_for_level(),
-                storage: robot.get_used_storage_space(),
-                max_storage: robot.levels.get_storage_for_level(),
-                mining_speed: robot.levels.get_mining_speed_for_level(),
-                mineable_resources: robot.get_mineable_resources(),
-                damage: robot.levels.get_damage_for_level(),
-                fighting_score: robot.get_fighting_score(),
-                money_value: robot.get_money_costs_for_robots_existing_upgrades(),
-                money_made: robot.money_made,
-            };
+            let robot_dto = get_robot_dto(robot, map);
             if robot.health > 0 {
                 alive.insert(robot.robot_id, robot_dto);
             } else {
@@ -856,12 +1456,23 @@ fn get_player_state_dto_from_gamestate(game_state: GameState, player_name: &str,
             (alive, dead)
         },
     );
+    let allied_robots: HashMap<Uuid, RobotDto> = game_state.get_allied_robots_for_current_round(player_name).unwrap_or_default().iter()
+        .filter(|robot| robot.is_alive())
+        .map(|robot| (robot.robot_id, get_robot_dto(robot, map)))
+        .collect();
+    let visited_planets = game_state.get_known_planets(player_name, round_number);
     // Compute planet data in parallel
-    let planet_map: HashMap<Uuid, PlanetPlayerDto> = map.indices.values().par_bridge().map(|&(x, y)| {
+    let planet_map: HashMap<Uuid, PlanetPlayerDto> = map.indices.values().par_bridge().filter_map(|&(x, y)| {
         let planet = map.planets[x][y].as_ref().unwrap();
-        let resource_data = planet.resources.as_ref().map(|(r, a)| (Some(r.clone()), *a)).unwrap_or((None, 0));
+        // Planets out of sight are shown as they were last seen, planets never seen are not shown at all
+        let planet_memory = if is_visible(&planet.planet_id) {
+            PlanetMemory::new(planet, round_number)
+        } else {
+            player_state.planet_memories.get(&planet.planet_id)?.clone()
+        };
+        let resource_data = planet_memory.resources.as_ref().map(|(r, a)| (Some(r.clone()), *a)).unwrap_or((None, 0));
 
-        let friendly_count_and_score = alive_robots.values().filter(|robot| robot.x == x && robot.y == y)
+        let friendly_count_and_score = alive_robots.values().chain(allied_robots.values()).filter(|robot| robot.x == x && robot.y == y)
             .fold((0, 0.0), |(count, score), robot| (count + 1, score + robot.fighting_score));
         let enemy_count_and_score = alive_enemy_robots.values().filter(|robot| robot.x == x && robot.y == y)
             .fold((0, 0.0), |(count, score), robot| (count + 1, score + robot.fighting_score));
@@ -872,55 +1483,89 @@ fn get_player_state_dto_from_gamestate(game_state: GameState, player_name: &str,
             movement_difficulty: planet.movement_difficulty,
             resource: resource_data.0,
             resource_amount: resource_data.1,
+            loot: planet_memory.loot,
             amount_of_friendly_robots: friendly_count_and_score.0 as u16,
             fighting_score_friendly_robots: friendly_count_and_score.1,
             amount_of_enemy_robots: enemy_count_and_score.0 as u16,
             fighting_score_enemy_robots: enemy_count_and_score.1,
             neighbours: planet.neighbours.clone(),
+            movement_costs: get_movement_costs(planet, map, &game_state.rules.movement_cost),
+            last_seen_round: planet_memory.last_seen_round,
         };
-        (planet.planet_id, planet_dto)
+        Some((planet.planet_id, planet_dto))
     }).collect();
 
     PlayerStateDto {
         current_round: game_state.current_round,
         player_name: player_state.player_name.clone(),
+        status: player_state.status.clone(),
         money: player_state.money.amount,
         total_money_made: player_state.total_money_made.amount,
+        team: game_state.get_team_of_player(player_name).to_string(),
+        teammates: game_state.get_teammates(player_name).into_iter().cloned().collect(),
         map: planet_map,
-        visited_planets: player_state.visited_planets.clone(),
+        visited_planets,
         alive_robots: alive_robots,
+        allied_robots,
         alive_enemy_robots: alive_enemy_robots,
         dead_robots: dead_robots,
-        killed_robots: player_state.killed_robots.iter().map(|(robot_id, killed_robots)| {
-            let robot_dtos = killed_robots.iter().map(|(enemy_player_name, robot)|
-                {
-                    let (x, y) = map.indices.get(&robot.planet_id).expect("Planet not found in indices");
-                    let robot_dto = RobotDto {
-                        x: *x,
-                        y: *y,
-                        robot_id: robot.robot_id,
-                        planet_id: robot.planet_id,
-                        health: robot.health,
-                        max_health: robot.levels.get_health_for_level(),
-                        energy: robot.energy,
-                        max_energy: robot.levels.get_energy_for_level(),
-                        energy_regen: robot.levels.get_energy_regen_for_level(),
-                        storage: robot.get_free_storage_space(),
-                        max_storage: robot.levels.get_storage_for_level(),
-                        mining_speed: robot.levels.get_mining_speed_for_level(),
-                        mineable_resources: robot.get_mineable_resources(),
-                        damage: robot.stats.damage,
-                        fighting_score: robot.get_fighting_score(),
-                        money_value: robot.get_money_costs_for_robots_existing_upgrades(),
-                        money_made: robot.money_made,
-                    };
-                    (enemy_player_name.clone(), robot_dto)
-                }).collect::<Vec<(String, RobotDto)>>();
-            (*robot_id, robot_dtos)
-        }).collect::<HashMap<Uuid, Vec<(String, RobotDto)>>>(),
+        killed_robots: get_killed_robot_dtos(&player_state.killed_robots, map),
+        assisted_kills: get_killed_robot_dtos(&player_state.assisted_kills, map),
+        rejected_commands: player_state.rejected_commands.clone(),
+        standing_orders: player_state.robots.values()
+            .filter_map(|robot| robot.standing_order.clone().map(|standing_order| (robot.robot_id, standing_order)))
+            .collect(),
+        enemy_intel: get_enemy_intel_dtos(player_state, round_number, map),
     }
 }
 
+fn get_enemy_intel_dtos(player_state: &PlayerState, round_number: u16, map: &GameMap) -> HashMap<Uuid, EnemyIntelDto> {
+    player_state.enemy_intel.iter().map(|(robot_id, enemy_intel)| {
+        let (x, y) = map.indices.get(&enemy_intel.planet_id).expect("Planet not found in indices");
+        (*robot_id, EnemyIntelDto {
+            player_name: enemy_intel.player_name.clone(),
+            x: *x,
+            y: *y,
+            planet_id: enemy_intel.planet_id,
+            last_seen_round: enemy_intel.last_seen_round,
+            rounds_since_seen: round_number.saturating_sub(enemy_intel.last_seen_round),
+            levels: enemy_intel.levels.clone(),
+            health: enemy_intel.health,
+            max_health: enemy_intel.levels.get_health_for_level(),
+        })
+    }).collect()
+}
+
+fn get_killed_robot_dtos(killed_robots: &HashMap<Uuid, Vec<(String, Robot)>>, map: &GameMap) -> HashMap<Uuid, Vec<(String, RobotDto)>> {
+    killed_robots.iter().map(|(robot_id, killed_robots)| {
+        let robot_dtos = killed_robots.iter().map(|(enemy_player_name, robot)|
+            {
+                let (x, y) = map.indices.get(&robot.planet_id).expect("Planet not found in indices");
+                let robot_dto = RobotDto {
+                    x: *x,
+                    y: *y,
+                    robot_id: robot.robot_id,
+                    planet_id: robot.planet_id,
+                    health: robot.health,
+                    max_health: robot.levels.get_health_for_level(),
+                    energy: robot.energy,
+                    max_energy: robot.levels.get_energy_for_level(),
+                    energy_regen: robot.levels.get_energy_regen_for_level(),
+                    storage: robot.get_free_storage_space(),
+                    max_storage: robot.levels.get_storage_for_level(),
+                    mining_speed: robot.levels.get_mining_speed_for_level(),
+                    mineable_resources: robot.get_mineable_resources(),
+                    damage: robot.stats.damage,
+                    fighting_score: robot.get_fighting_score(),
+                    money_value: robot.get_money_costs_for_robots_existing_upgrades(),
+                    money_made: robot.money_made,
+                };
+                (enemy_player_name.clone(), robot_dto)
+            }).collect::<Vec<(String, RobotDto)>>();
+        (*robot_id, robot_dtos)
+    }).collect::<HashMap<Uuid, Vec<(String, RobotDto)>>>()
+}
+
 #[actix_web::get("/games/{game_id}/currentRound/players/{player_name}/new")]
 async fn get_player_state_for_current_round_with_xy_for_planets(path: web::Path<(String, String)>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
     let (game_id, player_name) = path.into_inner();
@@ -990,7 +1635,7 @@ async fn handle_batch_of_commands_hypothetically(
         let round_state = game_state.round_states.get_mut(&current_round).unwrap();
         let player = round_state.player_name_player_map.get_mut(&player_name).unwrap();
 
-        for command in &new_commands {
+        for command in new_commands.iter().filter(|command| command.command_type != CommandType::END_TURN) {
             player.commands.entry(command.command_type)
                 .or_insert_with(VecDeque::new)
                 .push_back(command.clone());
@@ -1005,4 +1650,112 @@ async fn handle_batch_of_commands_hypothetically(
 
         Some(HttpResponse::Ok().json(player_state_dto))
     }).await.unwrap_or_else(|| HttpResponse::InternalServerError().body(format!("Game {} experienced an unknown error", &game_id)))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{add_players, create_game_state, create_robots};
+    use crate::trading::external::command_object::CommandObject;
+
+    use super::*;
+
+    /// Creates a game of alice and bob, who have two robots each, on a single planet.
+    fn create_game_of_two_players(turn_gate: TurnGate) -> GameState {
+        let planet = Planet::new(Uuid::new_v4(), 1);
+        let players = vec![("alice", create_robots(2, planet.planet_id)), ("bob", create_robots(2, planet.planet_id))];
+        create_game_state(players, vec![planet], GameRules { turn_gate, ..GameRules::default() })
+    }
+
+    fn submit_regenerate_command_for_one_robot(game_state: &mut GameState, player_name: &str) {
+        let player = game_state.get_player_for_current_round_as_mut(player_name).unwrap();
+        let robot_id = *player.robots.keys().next().unwrap();
+        player.submitted_commands = true;
+        player.commands.get_mut(&CommandType::REGENERATE).unwrap().push_back(Command {
+            player_name: player_name.to_string(),
+            game_id: String::new(),
+            command_type: CommandType::REGENERATE,
+            command_object: CommandObject { robot_id: Some(robot_id), planet_id: None, target_id: None, item_name: None, item_quantity: None },
+        });
+    }
+
+    #[test]
+    fn test_turn_gate_policies() {
+        let mut game_state = create_game_of_two_players(TurnGate::AllRobotsCovered);
+        submit_regenerate_command_for_one_robot(&mut game_state, "alice");
+        submit_regenerate_command_for_one_robot(&mut game_state, "bob");
+        assert!(!all_players_submitted_commands(&game_state));
+        game_state.rules.turn_gate = TurnGate::OneBatchPerPlayer;
+        assert!(all_players_submitted_commands(&game_state));
+        game_state.rules.turn_gate = TurnGate::EndTurnMarker;
+        assert!(!all_players_submitted_commands(&game_state));
+        game_state.get_player_for_current_round_as_mut("alice").unwrap().ended_turn = true;
+        game_state.get_player_for_current_round_as_mut("bob").unwrap().status = PlayerStatus::Disconnected;
+        assert!(all_players_submitted_commands(&game_state));
+    }
+
+    #[test]
+    fn test_turn_gate_does_not_wait_for_bots() {
+        let mut game_state = create_game_of_two_players(TurnGate::OneBatchPerPlayer);
+        game_state.get_player_for_current_round_as_mut("bob").unwrap().bot = Some(BotStrategy::GreedyMiner);
+        assert!(!all_players_submitted_commands(&game_state));
+        assert!(!only_bots_are_playing(&game_state));
+        submit_regenerate_command_for_one_robot(&mut game_state, "alice");
+        assert!(all_players_submitted_commands(&game_state));
+        game_state.forfeit_player("alice");
+        assert!(only_bots_are_playing(&game_state));
+    }
+
+    #[tokio::test]
+    async fn test_bots_play_game_to_the_end() {
+        let (planets, spawn_points) = MapGenerator::create_map_with_topology(15, &MapTopology::Grid, &MapSymmetry::None, &ResourceProfile::default(), &mut StdRng::seed_from_u64(3)).unwrap();
+        let mut game_state = GameState::new(Uuid::new_v4(), 60, 4, planets, GameRules::default(), 3);
+        game_state.round_states.get_mut(&0).unwrap().map.spawn_points = spawn_points;
+        let strategies = [("random", BotStrategy::Random), ("miner", BotStrategy::GreedyMiner), ("hunter", BotStrategy::AggressiveHunter), ("balanced", BotStrategy::BalancedEconomy)];
+        add_players(&mut game_state, strategies.iter().map(|(player_name, _)| (*player_name, vec![])).collect());
+        for (player_name, strategy) in strategies {
+            game_state.get_player_for_current_round_as_mut(player_name).unwrap().bot = Some(strategy);
+        }
+        game_state.status = GameStatus::Started;
+
+        assert!(all_players_submitted_commands(&game_state));
+        while game_state.status == GameStatus::Started && only_bots_are_playing(&game_state) {
+            game_state = process_commands_for_current_round(game_state, true, false).await.unwrap();
+            game_state.end_game_if_decided();
+        }
+        assert_eq!(game_state.status, GameStatus::Ended);
+        assert!(game_state.results.is_some());
+        let miner = game_state.get_player_for_round("miner", game_state.current_round).unwrap();
+        assert!(miner.total_money_made.amount > STARTING_MONEY);
+    }
+
+    #[test]
+    fn test_timeout_ends_round_of_merged_batches() {
+        let mut game_state = create_game_of_two_players(TurnGate::Timeout { seconds: 30 });
+        game_state.rules.submission_mode = SubmissionMode::Merge;
+        submit_regenerate_command_for_one_robot(&mut game_state, "alice");
+        assert!(!all_players_submitted_commands(&game_state));
+
+        end_round_after_timeout(&mut game_state);
+        assert!(game_state.get_player_for_current_round_as_mut("alice").unwrap().ended_turn);
+        assert_eq!(game_state.get_player_for_current_round_as_mut("bob").unwrap().status, PlayerStatus::Disconnected);
+        assert!(all_players_submitted_commands(&game_state));
+    }
+
+    #[test]
+    fn test_merged_batches_wait_for_ready() {
+        let mut game_state = create_game_of_two_players(TurnGate::OneBatchPerPlayer);
+        game_state.rules.submission_mode = SubmissionMode::Merge;
+        submit_regenerate_command_for_one_robot(&mut game_state, "alice");
+        submit_regenerate_command_for_one_robot(&mut game_state, "bob");
+        assert!(!all_players_submitted_commands(&game_state));
+        for player_name in ["alice", "bob"] {
+            game_state.get_player_for_current_round_as_mut(player_name).unwrap().ended_turn = true;
+        }
+        assert!(all_players_submitted_commands(&game_state));
+
+        let alice = game_state.get_player_for_current_round_as_mut("alice").unwrap();
+        let robot_id = *alice.robots.keys().next().unwrap();
+        remove_queued_commands(alice, |command| command.command_object.robot_id == Some(robot_id));
+        assert!(alice.commands.values().all(|commands| commands.is_empty()));
+    }
+}
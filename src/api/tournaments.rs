@@ -0,0 +1,279 @@
+use std::time::Duration;
+
+use actix_web::{HttpResponse, Responder, web};
+use actix_web::http::header::ContentType;
+use mobc::{Connection, Pool};
+use mobc_redis::redis::AsyncCommands;
+use mobc_redis::RedisConnectionManager;
+use serde_json::json;
+use tokio::time::sleep;
+use tracing::log::{error, info};
+use uuid::Uuid;
+
+use crate::api::games::{new_game_state_from_request, start_game_state, with_game_lock, with_lock, CreateGameRequestBody, STARTING_MONEY};
+use crate::bot::bot_strategy::BotStrategy;
+use crate::game::game_results::GameResults;
+use crate::game::game_state::GameStatus;
+use crate::player::PlayerState;
+use crate::tournament::tournament::{Tournament, TournamentStatus};
+use crate::tournament::tournament_format::TournamentFormat;
+
+/// How long a running tournament waits before it looks again for games which have ended.
+const ADVANCE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Tournaments are stored at `tournaments/{id}` and locked with `with_game_lock` like games, their ids are never the same.
+pub fn tournament_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_tournament)
+        .service(register_agent)
+        .service(start_tournament)
+        .service(get_tournament)
+        .service(get_games_of_agent);
+}
+
+#[derive(serde::Deserialize)]
+struct CreateTournamentRequestBody {
+    #[serde(default)]
+    format: TournamentFormat,
+    seeds: Vec<u64>,
+    game: CreateGameRequestBody,
+    #[serde(default)]
+    agents: Vec<RegisterAgentRequestBody>,
+}
+
+#[derive(serde::Deserialize)]
+struct RegisterAgentRequestBody {
+    name: String,
+    bot: Option<BotStrategy>, // Registers a built-in bot, without it the agent is external and plays its games through the API
+}
+
+/// The part of a stored game a tournament needs, so that the round states of the game don't have to be read.
+#[derive(serde::Deserialize)]
+struct GameOutcome {
+    status: GameStatus,
+    results: Option<GameResults>,
+}
+
+#[actix_web::post("/tournaments")]
+async fn create_tournament(body: web::Json<CreateTournamentRequestBody>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let body = body.into_inner();
+    let mut tournament = match Tournament::new(body.format, body.seeds, body.game) {
+        Ok(tournament) => tournament,
+        Err(error) => return HttpResponse::BadRequest().body(format!("Tournament could not be created: {error}")),
+    };
+    // The map of every seed is created once now, so that creating the games can't fail once the tournament runs
+    for seed in &tournament.seeds {
+        if let Err(error) = new_game_state_from_request(&tournament.game, *seed) {
+            return HttpResponse::BadRequest().body(format!("Map of seed {seed} could not be created: {error}"));
+        }
+    }
+    for agent in body.agents {
+        if let Err(error) = tournament.add_agent(&agent.name, agent.bot) {
+            return HttpResponse::BadRequest().body(format!("Tournament could not be created: {error}"));
+        }
+    }
+    let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+    if let Err(response) = save_tournament(&mut con, &tournament).await {
+        return response;
+    }
+    HttpResponse::Created().insert_header(ContentType::json()).body(json!({
+        "tournament_id": tournament.tournament_id,
+    }).to_string())
+}
+
+#[actix_web::put("/tournaments/{tournament_id}/agents")]
+async fn register_agent(body: web::Json<RegisterAgentRequestBody>, path: web::Path<String>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let tournament_id = path.into_inner();
+    with_game_lock(&redis_client, &tournament_id, || async {
+        let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+        let mut tournament = load_tournament(&mut con, &tournament_id).await?;
+        if let Err(error) = tournament.add_agent(&body.name, body.bot.clone()) {
+            return Some(HttpResponse::BadRequest().body(format!("Agent can't be registered: {error}")));
+        }
+        if let Err(response) = save_tournament(&mut con, &tournament).await {
+            return Some(response);
+        }
+        Some(HttpResponse::Ok().insert_header(ContentType::json()).body(json!({
+            "tournament_id": tournament_id,
+            "name": body.name,
+            "bot": body.bot,
+        }).to_string()))
+    }).await.unwrap_or(HttpResponse::NotFound().body(format!("Tournament {tournament_id} was not found.")))
+}
+
+#[actix_web::post("/tournaments/{tournament_id}/start")]
+async fn start_tournament(path: web::Path<String>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let tournament_id = path.into_inner();
+    with_game_lock(&redis_client, &tournament_id, || async {
+        let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+        let mut tournament = load_tournament(&mut con, &tournament_id).await?;
+        if tournament.status != TournamentStatus::Created {
+            return Some(HttpResponse::BadRequest().body(format!("Tournament {} can't be started because it is currently in status {:?}", &tournament_id, &tournament.status)));
+        }
+        if let Err(error) = tournament.start_next_round() {
+            return Some(HttpResponse::BadRequest().body(format!("Tournament can't be started: {error}")));
+        }
+        if let Err(response) = save_tournament(&mut con, &tournament).await {
+            return Some(response);
+        }
+        info!("Started tournament {} with {} agents", &tournament_id, tournament.agents.len());
+        run_tournament(redis_client.clone(), tournament_id.clone());
+        Some(HttpResponse::Ok().json(get_tournament_dto(&tournament)))
+    }).await.unwrap_or(HttpResponse::NotFound().body(format!("Tournament {tournament_id} was not found.")))
+}
+
+/// Returns the standings, which the tournament keeps up to date while it runs.
+#[actix_web::get("/tournaments/{tournament_id}")]
+async fn get_tournament(path: web::Path<String>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let tournament_id = path.into_inner();
+    let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+    match load_tournament(&mut con, &tournament_id).await {
+        Some(tournament) => HttpResponse::Ok().json(get_tournament_dto(&tournament)),
+        None => HttpResponse::NotFound().body(format!("Tournament {tournament_id} was not found.")),
+    }
+}
+
+/// Lets an external agent find the games it has to play, it joins none of them itself.
+#[actix_web::get("/tournaments/{tournament_id}/agents/{name}/games")]
+async fn get_games_of_agent(path: web::Path<(String, String)>, redis_client: web::Data<Pool<RedisConnectionManager>>) -> impl Responder {
+    let (tournament_id, name) = path.into_inner();
+    let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+    let Some(tournament) = load_tournament(&mut con, &tournament_id).await else {
+        return HttpResponse::NotFound().body(format!("Tournament {tournament_id} was not found."));
+    };
+    if tournament.get_agent(&name).is_none() {
+        return HttpResponse::NotFound().body(format!("Agent {} is not registered in tournament {}", &name, &tournament_id));
+    }
+    let games: Vec<serde_json::Value> = tournament.matches.iter()
+        .filter(|tournament_match| tournament_match.agents.contains(&name))
+        .map(|tournament_match| json!({
+            "game_id": tournament_match.game_id,
+            "round": tournament_match.round,
+            "seed": tournament_match.seed,
+            "opponent": tournament_match.agents.iter().find(|agent| **agent != name),
+            "result": tournament_match.result,
+        }))
+        .collect();
+    HttpResponse::Ok().json(json!({
+        "tournament_status": tournament.status,
+        "games": games,
+    }))
+}
+
+/// Advances the tournament in the background until it has ended, see `advance_and_save_tournament`. The lock of the
+/// tournament is only taken for a single step, the games themselves are played by their players or bots meanwhile.
+fn run_tournament(redis_client: web::Data<Pool<RedisConnectionManager>>, tournament_id: String) {
+    actix_web::rt::spawn(async move {
+        loop {
+            let is_running = with_lock(&redis_client, &tournament_id, || async {
+                let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+                let mut tournament = load_tournament(&mut con, &tournament_id).await?;
+                // A step which failed is tried again with the next one
+                if let Err(message) = advance_and_save_tournament(&mut tournament, &redis_client, &mut con).await {
+                    error!("Tournament {} could not be advanced: {}", &tournament_id, message);
+                }
+                Some(tournament.status == TournamentStatus::Running)
+            }).await.unwrap_or(false);
+            if !is_running {
+                info!("Tournament {} has ended", &tournament_id);
+                break;
+            }
+            sleep(ADVANCE_INTERVAL).await;
+        }
+    });
+}
+
+/// Records the results of the games which have ended, creates and starts the games of the current round and
+/// starts the next round once every game of the current one has ended.
+async fn advance_and_save_tournament(tournament: &mut Tournament, redis_client: &web::Data<Pool<RedisConnectionManager>>, con: &mut Connection<RedisConnectionManager>) -> Result<(), String> {
+    while tournament.status == TournamentStatus::Running {
+        for match_index in 0..tournament.matches.len() {
+            let tournament_match = &tournament.matches[match_index];
+            if tournament_match.result.is_some() {
+                continue;
+            }
+            let Some(game_id) = tournament_match.game_id else {
+                let game_id = start_tournament_game(tournament, match_index, redis_client).await?;
+                tournament.matches[match_index].game_id = Some(game_id);
+                continue;
+            };
+            // A game which can't be read right now is looked at again in the next step
+            let Ok(game) = con.get::<_, Option<String>>(format!("games/{}", game_id)).await else { continue };
+            let Some(game) = game else {
+                // The game was deleted before it ended, so it can't decide the match anymore
+                info!("Game {} of tournament {} no longer exists and counts as a draw", game_id, tournament.tournament_id);
+                tournament.record_missing_game(match_index);
+                continue;
+            };
+            let game_outcome = serde_json::from_str::<GameOutcome>(&game).unwrap();
+            if game_outcome.status == GameStatus::Ended {
+                if let Some(results) = game_outcome.results {
+                    tournament.record_result(match_index, &results);
+                }
+            }
+        }
+        if !tournament.is_current_round_finished() {
+            break;
+        }
+        tournament.start_next_round().map_err(|error| format!("Next round can't be started: {error}"))?;
+    }
+    save_tournament(con, tournament).await.map_err(|_| format!("Failed to save tournament {} to Redis", tournament.tournament_id))
+}
+
+/// Creates the game of a match, lets both agents join it, starts and saves it. The game is locked until it is saved,
+/// so that built-in bots don't start to play it before it is stored.
+async fn start_tournament_game(tournament: &Tournament, match_index: usize, redis_client: &web::Data<Pool<RedisConnectionManager>>) -> Result<Uuid, String> {
+    let tournament_match = &tournament.matches[match_index];
+    let mut game_state = new_game_state_from_request(&tournament.game, tournament_match.seed)
+        .map_err(|error| format!("Map could not be created: {error}"))?;
+    for agent_name in &tournament_match.agents {
+        let mut player = PlayerState::new(agent_name.clone(), STARTING_MONEY);
+        player.bot = tournament.get_agent(agent_name).and_then(|agent| agent.bot.clone());
+        game_state.add_player(player, None);
+    }
+    info!("Started game {} of tournament {} between {} and {}", game_state.game_id, tournament.tournament_id, tournament_match.agents[0], tournament_match.agents[1]);
+    let game_id = game_state.game_id;
+    with_lock(redis_client, &game_id.to_string(), move || async move {
+        let game_state = start_game_state(game_state, redis_client);
+        let mut con = redis_client.get().await.expect("Failed to get Redis connection from pool");
+        let is_write_successful: bool = con.set(format!("games/{}", game_id), serde_json::to_string(&game_state).unwrap()).await.unwrap_or(false);
+        (!is_write_successful).then(|| format!("Failed to save game {} to Redis", game_id))
+    }).await.map_or(Ok(game_id), Err)
+}
+
+fn get_tournament_dto(tournament: &Tournament) -> serde_json::Value {
+    let standings: Vec<serde_json::Value> = tournament.get_standings().into_iter().enumerate()
+        .map(|(index, agent)| json!({
+            "rank": index + 1,
+            "name": agent.name,
+            "bot": agent.bot,
+            "rating": agent.rating.round(),
+            "points": agent.points,
+            "wins": agent.wins,
+            "draws": agent.draws,
+            "losses": agent.losses,
+            "byes": agent.byes,
+        }))
+        .collect();
+    json!({
+        "tournament_id": tournament.tournament_id,
+        "status": tournament.status,
+        "format": tournament.format,
+        "seeds": tournament.seeds,
+        "current_round": tournament.current_round,
+        "standings": standings,
+        "matches": tournament.matches,
+    })
+}
+
+async fn load_tournament(con: &mut Connection<RedisConnectionManager>, tournament_id: &str) -> Option<Tournament> {
+    let tournament: Option<String> = con.get(format!("tournaments/{}", tournament_id)).await.unwrap_or(None);
+    Some(serde_json::from_str(&tournament?).unwrap())
+}
+
+async fn save_tournament(con: &mut Connection<RedisConnectionManager>, tournament: &Tournament) -> Result<(), HttpResponse> {
+    let is_write_successful: bool = con.set(format!("tournaments/{}", tournament.tournament_id), serde_json::to_string(tournament).unwrap()).await.unwrap_or(false);
+    if !is_write_successful {
+        return Err(HttpResponse::InternalServerError().body(format!("Failed to save tournament {} to Redis", tournament.tournament_id)));
+    }
+    Ok(())
+}
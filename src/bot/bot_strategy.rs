@@ -0,0 +1,349 @@
+use std::collections::HashSet;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use tracing::log::debug;
+use uuid::Uuid;
+
+use crate::bot::bot_view::{get_attack_energy_cost, BotView};
+use crate::game::game_state::GameState;
+use crate::planet::planet_memory::PlanetMemory;
+use crate::player::PlayerStatus;
+use crate::robot::robot::Robot;
+use crate::robot::robot_level::RobotLevel;
+use crate::robot::robot_levels::RobotLevels;
+use crate::trading::external::command::Command;
+use crate::trading::external::command_type::CommandType;
+use crate::trading::external::handler::buy_command_handler::{Item, UpgradeType};
+
+const MAX_ROBOTS: usize = 8; // Bots don't buy more robots than this, so they keep their money for upgrades
+
+/// How a built-in bot plays. Bots decide on their commands right before a round is processed, see `queue_bot_commands`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum BotStrategy {
+    /// Picks a random action for every robot and spends its money at random.
+    Random,
+    /// Mines the most valuable resources it can reach and sells once the storage is full.
+    /// Spends its money on more robots first and on mining upgrades afterwards.
+    GreedyMiner,
+    /// Attacks the weakest enemy robot in range and chases the closest enemy robot it knows about.
+    /// Spends its money on damage and health upgrades first. Mines while it doesn't know about any enemy robot.
+    AggressiveHunter,
+    /// Mines like the greedy miner, but only fights enemy robots which are not stronger than its own robot.
+    /// Keeps enough money to replace a robot, restores damaged robots and spreads its upgrades.
+    BalancedEconomy,
+}
+
+impl BotStrategy {
+    pub fn get_commands(&self, view: &BotView, rng: &mut StdRng) -> Vec<Command> {
+        // Planets a robot is already mining or heading to, so that the other robots look for their own
+        let mut claimed_planets = HashSet::new();
+        let mut commands = Vec::new();
+        for robot in &view.robots {
+            commands.push(match self {
+                BotStrategy::Random => get_random_command(view, robot, rng),
+                BotStrategy::GreedyMiner => get_mining_command(view, robot, &mut claimed_planets, rng),
+                BotStrategy::AggressiveHunter => get_hunting_command(view, robot, &mut claimed_planets, rng),
+                BotStrategy::BalancedEconomy => get_attack_command(view, robot, |enemy_robot| enemy_robot.get_fighting_score() <= robot.get_fighting_score())
+                    .unwrap_or_else(|| get_mining_command(view, robot, &mut claimed_planets, rng)),
+            });
+        }
+        commands.extend(self.get_buy_commands(view, rng));
+        commands
+    }
+
+    fn get_buy_commands(&self, view: &BotView, rng: &mut StdRng) -> Vec<Command> {
+        let robot_cost = Item::Robot(1).get_cost();
+        let mut budget = view.money;
+        let mut robot_count = view.robots.len();
+        let mut commands = Vec::new();
+        let buy_robot = |budget: &mut u32, robot_count: &mut usize, commands: &mut Vec<Command>| {
+            *budget -= robot_cost;
+            *robot_count += 1;
+            commands.push(view.buy("robot", None));
+        };
+        // A bot without robots can't do anything else
+        if robot_count == 0 && budget >= robot_cost {
+            buy_robot(&mut budget, &mut robot_count, &mut commands);
+        }
+        match self {
+            BotStrategy::Random => {
+                if budget >= robot_cost && robot_count < MAX_ROBOTS && rng.gen_bool(0.25) {
+                    buy_robot(&mut budget, &mut robot_count, &mut commands);
+                } else if rng.gen_bool(0.25) {
+                    let upgrade_types = [UpgradeType::Health, UpgradeType::Energy, UpgradeType::EnergyRegen, UpgradeType::Damage, UpgradeType::Mining, UpgradeType::MiningSpeed, UpgradeType::Storage];
+                    let upgrade_type = upgrade_types.choose(rng).unwrap().clone();
+                    commands.extend(buy_cheapest_upgrade(view, &[upgrade_type], &mut budget, 0));
+                }
+            }
+            BotStrategy::GreedyMiner => {
+                while budget >= robot_cost && robot_count < MAX_ROBOTS {
+                    buy_robot(&mut budget, &mut robot_count, &mut commands);
+                }
+                commands.extend(buy_cheapest_upgrade(view, &[UpgradeType::Mining, UpgradeType::MiningSpeed, UpgradeType::Storage], &mut budget, 0));
+            }
+            BotStrategy::AggressiveHunter => {
+                commands.extend(buy_cheapest_upgrade(view, &[UpgradeType::Damage, UpgradeType::Health], &mut budget, 0));
+                while budget >= robot_cost && robot_count < MAX_ROBOTS {
+                    buy_robot(&mut budget, &mut robot_count, &mut commands);
+                }
+            }
+            BotStrategy::BalancedEconomy => {
+                let health_restore_cost = Item::HealthRestore.get_cost();
+                for robot in view.robots.iter().filter(|robot| robot.health < robot.levels.get_health_for_level() / 2) {
+                    if budget >= health_restore_cost + robot_cost {
+                        budget -= health_restore_cost;
+                        commands.push(view.buy("health_restore", Some(robot.robot_id)));
+                    }
+                }
+                if budget >= 2 * robot_cost && robot_count < MAX_ROBOTS / 2 {
+                    buy_robot(&mut budget, &mut robot_count, &mut commands);
+                }
+                commands.extend(buy_cheapest_upgrade(view, &[UpgradeType::Mining, UpgradeType::Storage, UpgradeType::MiningSpeed, UpgradeType::Damage, UpgradeType::Health], &mut budget, robot_cost));
+            }
+        }
+        commands
+    }
+}
+
+/// Lets every active built-in bot queue its commands for the current round. This is done right before the round
+/// is processed, so bots always act on the latest state and nobody has to wait for them.
+pub fn queue_bot_commands(game_state: &mut GameState) {
+    let current_round = game_state.current_round;
+    let Some(round_state) = game_state.round_states.get(&current_round) else { return };
+    let bots: Vec<(usize, String, BotStrategy)> = game_state.participating_players.iter().enumerate()
+        .filter_map(|(index, player_name)| {
+            let player = round_state.player_name_player_map.get(player_name)?;
+            let strategy = player.bot.clone().filter(|_| player.status == PlayerStatus::Active)?;
+            Some((index, player_name.clone(), strategy))
+        })
+        .collect();
+
+    let mut commands_per_bot = Vec::new();
+    for (index, player_name, strategy) in bots {
+        // Seeded like the player order, so that replaying a seed replays the bots as well
+        let mut rng = StdRng::seed_from_u64(game_state.seed ^ (current_round as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (index as u64 + 1).wrapping_mul(0xBF58_476D_1CE4_E5B9));
+        let Some(view) = BotView::new(game_state, &player_name) else { continue };
+        let commands = strategy.get_commands(&view, &mut rng);
+        commands_per_bot.push((player_name, commands));
+    }
+    for (player_name, commands) in commands_per_bot {
+        let Some(player) = game_state.get_player_for_current_round_as_mut(&player_name) else { continue };
+        debug!("Bot {} queued commands: {:?}", player_name, commands);
+        for command in commands {
+            player.commands.entry(command.command_type).or_default().push_back(command);
+        }
+    }
+}
+
+fn get_random_command(view: &BotView, robot: &Robot, rng: &mut StdRng) -> Command {
+    let mut options = vec![view.command(CommandType::REGENERATE, robot.robot_id, None)];
+    for neighbour_id in get_affordable_neighbours(view, robot) {
+        options.push(view.command(CommandType::MOVEMENT, robot.robot_id, Some(neighbour_id)));
+    }
+    if view.can_mine(robot, &robot.planet_id) {
+        options.push(view.command(CommandType::MINING, robot.robot_id, Some(robot.planet_id)));
+    }
+    if robot.get_used_storage_space() > 0 {
+        options.push(view.command(CommandType::SELLING, robot.robot_id, None));
+    }
+    if robot.energy >= get_attack_energy_cost(robot) {
+        for enemy_robot in view.get_enemy_robots_in_range(robot) {
+            options.push(view.command(CommandType::BATTLE, robot.robot_id, Some(enemy_robot.robot_id)));
+        }
+    }
+    options.swap_remove(rng.gen_range(0..options.len()))
+}
+
+/// Mines on the current planet, or heads to the most valuable planet the robot can mine on, compared to the energy it costs to get there.
+/// Sells once the storage is full and explores when it doesn't know anything worth mining.
+fn get_mining_command(view: &BotView, robot: &Robot, claimed_planets: &mut HashSet<Uuid>, rng: &mut StdRng) -> Command {
+    if robot.is_storage_full() {
+        return view.command(CommandType::SELLING, robot.robot_id, None);
+    }
+    if view.can_mine(robot, &robot.planet_id) {
+        claimed_planets.insert(robot.planet_id);
+        return view.command(CommandType::MINING, robot.robot_id, Some(robot.planet_id));
+    }
+    let energy_costs = view.get_energy_costs(robot);
+    let destination = view.planets.iter()
+        .filter(|(planet_id, _)| !claimed_planets.contains(*planet_id) && view.can_mine(robot, planet_id))
+        .filter_map(|(planet_id, planet)| Some((get_planet_value(robot, planet) as u64 * 1000 / (*energy_costs.get(planet_id)? as u64 + 1), *planet_id)))
+        .max()
+        .map(|(_, planet_id)| planet_id);
+    if let Some(destination) = destination {
+        claimed_planets.insert(destination);
+        if let Some(command) = view.get_step_towards(robot, destination) {
+            return command;
+        }
+    }
+    if robot.get_used_storage_space() > 0 {
+        return view.command(CommandType::SELLING, robot.robot_id, None);
+    }
+    get_exploring_command(view, robot, rng)
+}
+
+fn get_hunting_command(view: &BotView, robot: &Robot, claimed_planets: &mut HashSet<Uuid>, rng: &mut StdRng) -> Command {
+    if let Some(command) = get_attack_command(view, robot, |_| true) {
+        return command;
+    }
+    if robot.energy < robot.levels.get_energy_for_level() / 2 {
+        return view.command(CommandType::REGENERATE, robot.robot_id, None);
+    }
+    let energy_costs = view.get_energy_costs(robot);
+    let closest_enemy_planet = view.enemy_robots.iter().map(|enemy_robot| enemy_robot.planet_id)
+        .chain(view.enemy_sightings.iter().copied())
+        .filter_map(|planet_id| Some((*energy_costs.get(&planet_id)?, planet_id)))
+        .min();
+    if let Some(command) = closest_enemy_planet.and_then(|(_, planet_id)| view.get_step_towards(robot, planet_id)) {
+        return command;
+    }
+    get_mining_command(view, robot, claimed_planets, rng)
+}
+
+/// Attacks the enemy robot in range with the least health, of those which `should_attack` accepts.
+fn get_attack_command(view: &BotView, robot: &Robot, should_attack: impl Fn(&Robot) -> bool) -> Option<Command> {
+    if robot.energy < get_attack_energy_cost(robot) {
+        return None;
+    }
+    let target = view.get_enemy_robots_in_range(robot).into_iter()
+        .filter(|enemy_robot| should_attack(enemy_robot))
+        .min_by_key(|enemy_robot| (enemy_robot.health, enemy_robot.robot_id))?;
+    Some(view.command(CommandType::BATTLE, robot.robot_id, Some(target.robot_id)))
+}
+
+/// Moves to a random neighbour the robot has the energy for, or regenerates.
+fn get_exploring_command(view: &BotView, robot: &Robot, rng: &mut StdRng) -> Command {
+    match get_affordable_neighbours(view, robot).choose(rng) {
+        Some(neighbour_id) => view.command(CommandType::MOVEMENT, robot.robot_id, Some(*neighbour_id)),
+        None => view.command(CommandType::REGENERATE, robot.robot_id, None),
+    }
+}
+
+fn get_affordable_neighbours(view: &BotView, robot: &Robot) -> Vec<Uuid> {
+    view.get_neighbours(&robot.planet_id).into_iter()
+        .filter(|neighbour_id| view.get_movement_cost(&robot.planet_id, neighbour_id).is_some_and(|movement_cost| movement_cost <= robot.energy))
+        .collect()
+}
+
+/// The selling value of one unit the robot can mine or pick up on the planet.
+fn get_planet_value(robot: &Robot, planet: &PlanetMemory) -> u32 {
+    let resource_value = planet.resources.as_ref()
+        .filter(|(resource, amount)| *amount > 0 && robot.get_mineable_resources().contains(resource))
+        .map(|(resource, _)| resource.get_selling_value())
+        .unwrap_or(0);
+    let loot_value = planet.loot.keys().map(|resource| resource.get_selling_value()).max().unwrap_or(0);
+    resource_value.max(loot_value)
+}
+
+/// Buys the cheapest of the given upgrades for one of the robots, if that leaves at least `reserve` of the budget.
+fn buy_cheapest_upgrade(view: &BotView, upgrade_types: &[UpgradeType], budget: &mut u32, reserve: u32) -> Option<Command> {
+    let (cost, robot_id, item_name) = view.robots.iter()
+        .flat_map(|robot| upgrade_types.iter().filter_map(|upgrade_type| {
+            let (upgrade_name, level) = get_upgrade_level(robot, upgrade_type);
+            let next_level = level.next()?;
+            Some((RobotLevels::get_cost_for_level(&next_level), robot.robot_id, format!("{}_{}", upgrade_name, next_level.get_int_value())))
+        }))
+        .min()?;
+    if *budget < cost + reserve {
+        return None;
+    }
+    *budget -= cost;
+    Some(view.buy(&item_name, Some(robot_id)))
+}
+
+/// Returns the item name of the upgrade, see `parse_item_name`, and the robot's current level of it.
+fn get_upgrade_level<'a>(robot: &'a Robot, upgrade_type: &UpgradeType) -> (&'static str, &'a RobotLevel) {
+    match upgrade_type {
+        UpgradeType::Health => ("health", &robot.levels.health_level),
+        UpgradeType::Energy => ("energy", &robot.levels.energy_level),
+        UpgradeType::EnergyRegen => ("energy_regen", &robot.levels.energy_regen_level),
+        UpgradeType::Damage => ("damage", &robot.levels.damage_level),
+        UpgradeType::Mining => ("mining", &robot.levels.mining_level),
+        UpgradeType::MiningSpeed => ("mining_speed", &robot.levels.mining_speed_level),
+        UpgradeType::Storage => ("storage", &robot.levels.storage_level),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::game_rules::GameRules;
+    use crate::planet::direction::Direction;
+    use crate::planet::planet::Planet;
+    use crate::planet::resource::Resource;
+    use crate::test_support::create_game_state;
+
+    use super::*;
+
+    /// Creates a game in which the bots have no money, so that they don't buy anything.
+    fn create_game_state_without_money(players: Vec<(&str, Vec<Robot>)>, planets: Vec<Planet>) -> GameState {
+        let mut game_state = create_game_state(players, planets, GameRules::default());
+        for player in game_state.round_states.get_mut(&0).unwrap().player_name_player_map.values_mut() {
+            player.money.amount = 0;
+        }
+        game_state
+    }
+
+    fn get_commands(game_state: &GameState, player_name: &str, strategy: BotStrategy) -> Vec<Command> {
+        let view = BotView::new(game_state, player_name).unwrap();
+        strategy.get_commands(&view, &mut StdRng::seed_from_u64(0))
+    }
+
+    #[test]
+    fn test_greedy_miner_heads_to_resources_and_sells_when_full() {
+        let mut empty_planet = Planet::new(Uuid::new_v4(), 1);
+        let mut coal_planet = Planet::new(Uuid::new_v4(), 1);
+        coal_planet.resources = Some((Resource::COAL, 100));
+        empty_planet.set_neighbour(Direction::SOUTH, coal_planet.planet_id);
+        coal_planet.set_neighbour(Direction::NORTH, empty_planet.planet_id);
+        let (empty_planet_id, coal_planet_id) = (empty_planet.planet_id, coal_planet.planet_id);
+        let mut robot = Robot::new(Uuid::new_v4(), empty_planet_id);
+        let robot_id = robot.robot_id;
+        robot.stats.max_storage = 10;
+        let mut game_state = create_game_state_without_money(vec![("miner", vec![robot])], vec![empty_planet, coal_planet]);
+
+        let commands = get_commands(&game_state, "miner", BotStrategy::GreedyMiner);
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].command_type, CommandType::MOVEMENT);
+        assert_eq!(commands[0].command_object.target_id, Some(coal_planet_id));
+
+        let robot = game_state.get_robot_for_current_round_by_player_id_and_robot_id("miner", &robot_id).unwrap();
+        robot.planet_id = coal_planet_id;
+        assert_eq!(get_commands(&game_state, "miner", BotStrategy::GreedyMiner)[0].command_type, CommandType::MINING);
+
+        let robot = game_state.get_robot_for_current_round_by_player_id_and_robot_id("miner", &robot_id).unwrap();
+        robot.add_resource_to_inventory(&Resource::COAL, &10);
+        assert_eq!(get_commands(&game_state, "miner", BotStrategy::GreedyMiner)[0].command_type, CommandType::SELLING);
+
+        // Money left after the robot was sold off is spent on new robots
+        let miner = game_state.get_player_for_current_round_as_mut("miner").unwrap();
+        miner.robots.clear();
+        miner.money.amount = 250;
+        let commands = get_commands(&game_state, "miner", BotStrategy::GreedyMiner);
+        assert_eq!(commands.iter().filter(|command| command.command_object.item_name.as_deref() == Some("robot")).count(), 2);
+    }
+
+    #[test]
+    fn test_strategies_decide_whom_to_attack() {
+        let planet = Planet::new(Uuid::new_v4(), 1);
+        let hunter_robot = Robot::new(Uuid::new_v4(), planet.planet_id);
+        let mut weak_enemy = Robot::new(Uuid::new_v4(), planet.planet_id);
+        weak_enemy.health = 1;
+        let mut strong_enemy = Robot::new(Uuid::new_v4(), planet.planet_id);
+        strong_enemy.energy = 1000;
+        let weak_enemy_id = weak_enemy.robot_id;
+        let game_state = create_game_state_without_money(vec![("hunter", vec![hunter_robot.clone()]), ("prey", vec![weak_enemy, strong_enemy.clone()])], vec![planet.clone()]);
+
+        let commands = get_commands(&game_state, "hunter", BotStrategy::AggressiveHunter);
+        assert_eq!(commands[0].command_type, CommandType::BATTLE);
+        assert_eq!(commands[0].command_object.target_id, Some(weak_enemy_id));
+        assert_eq!(get_commands(&game_state, "hunter", BotStrategy::BalancedEconomy)[0].command_type, CommandType::BATTLE);
+
+        // The balanced bot doesn't take on a stronger robot
+        let game_state = create_game_state_without_money(vec![("hunter", vec![hunter_robot]), ("prey", vec![strong_enemy])], vec![planet]);
+        assert_eq!(get_commands(&game_state, "hunter", BotStrategy::AggressiveHunter)[0].command_type, CommandType::BATTLE);
+        assert_ne!(get_commands(&game_state, "hunter", BotStrategy::BalancedEconomy)[0].command_type, CommandType::BATTLE);
+    }
+}
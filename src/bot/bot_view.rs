@@ -0,0 +1,146 @@
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::game::game_rules::{AttackRange, GameRules};
+use crate::game::game_state::{GameMap, GameState};
+use crate::planet::pathfinding::{find_cheapest_path, get_energy_costs_from};
+use crate::planet::planet_memory::PlanetMemory;
+use crate::robot::robot::Robot;
+use crate::trading::external::command::Command;
+use crate::trading::external::command_object::CommandObject;
+use crate::trading::external::command_type::CommandType;
+
+/// What a built-in bot knows about the game, when it decides on its commands. This is what a player gets
+/// in their `PlayerStateDto`: their own robots, the planets in sight or remembered and the enemy robots in sight.
+pub struct BotView<'a> {
+    pub game_id: String,
+    pub player_name: &'a str,
+    pub money: u32,
+    pub rules: &'a GameRules,
+    pub robots: Vec<&'a Robot>, // Alive robots of the bot, ordered by id
+    pub enemy_robots: Vec<&'a Robot>, // Alive enemy robots in sight, ordered by id
+    pub enemy_sightings: Vec<Uuid>, // Planets on which enemy robots out of sight were seen last
+    pub planets: HashMap<Uuid, PlanetMemory>, // Planets in sight as they are now, all other known planets as they were last seen
+    known_planets: Option<HashSet<Uuid>>, // Planets whose neighbours the bot knows, None if it can see the whole map
+    map: &'a GameMap,
+}
+
+impl<'a> BotView<'a> {
+    pub fn new(game_state: &'a GameState, player_name: &'a str) -> Option<BotView<'a>> {
+        let current_round = game_state.current_round;
+        let round_state = game_state.round_states.get(&current_round)?;
+        let player = round_state.player_name_player_map.get(player_name)?;
+        let visible_planets = game_state.get_visible_planets(player_name, current_round);
+        let is_visible = |planet_id: &Uuid| visible_planets.as_ref().is_none_or(|visible_planets| visible_planets.contains(planet_id));
+
+        let mut robots: Vec<&Robot> = player.robots.values().filter(|robot| robot.is_alive()).collect();
+        robots.sort_by_key(|robot| robot.robot_id);
+        let mut enemy_robots: Vec<&Robot> = game_state.get_enemy_robots_for_current_round(player_name).unwrap_or_default().into_iter()
+            .filter(|robot| robot.is_alive() && is_visible(&robot.planet_id))
+            .collect();
+        enemy_robots.sort_by_key(|robot| robot.robot_id);
+        let mut enemy_sightings: Vec<Uuid> = player.enemy_intel.values()
+            .map(|enemy_intel| enemy_intel.planet_id)
+            .filter(|planet_id| !is_visible(planet_id))
+            .collect();
+        enemy_sightings.sort();
+        enemy_sightings.dedup();
+
+        let planets: HashMap<Uuid, PlanetMemory> = round_state.map.indices.keys().filter_map(|planet_id| {
+            let planet_memory = if is_visible(planet_id) {
+                PlanetMemory::new(round_state.map.get_planet(planet_id)?, current_round)
+            } else {
+                player.planet_memories.get(planet_id)?.clone()
+            };
+            Some((*planet_id, planet_memory))
+        }).collect();
+        let known_planets = visible_planets.map(|_| planets.keys().copied().collect());
+
+        Some(BotView {
+            game_id: game_state.game_id.to_string(),
+            player_name,
+            money: player.money.amount,
+            rules: &game_state.rules,
+            robots,
+            enemy_robots,
+            enemy_sightings,
+            planets,
+            known_planets,
+            map: &round_state.map,
+        })
+    }
+
+    pub fn command(&self, command_type: CommandType, robot_id: Uuid, target_id: Option<Uuid>) -> Command {
+        Command {
+            player_name: self.player_name.to_string(),
+            game_id: self.game_id.clone(),
+            command_type,
+            command_object: CommandObject { robot_id: Some(robot_id), planet_id: None, target_id, item_name: None, item_quantity: None },
+        }
+    }
+
+    /// Buys an item, `robot_id` is the robot which gets an upgrade or a restore.
+    pub fn buy(&self, item_name: &str, robot_id: Option<Uuid>) -> Command {
+        Command {
+            player_name: self.player_name.to_string(),
+            game_id: self.game_id.clone(),
+            command_type: CommandType::BUYING,
+            command_object: CommandObject { robot_id, planet_id: None, target_id: None, item_name: Some(item_name.to_string()), item_quantity: Some(1) },
+        }
+    }
+
+    /// Returns the neighbours of a planet, ordered by id.
+    pub fn get_neighbours(&self, planet_id: &Uuid) -> Vec<Uuid> {
+        let mut neighbours: Vec<Uuid> = self.map.get_planet(planet_id)
+            .map(|planet| planet.neighbours.values().copied().collect())
+            .unwrap_or_default();
+        neighbours.sort();
+        neighbours
+    }
+
+    pub fn get_movement_cost(&self, from: &Uuid, to: &Uuid) -> Option<u32> {
+        Some(self.rules.movement_cost.get_movement_cost(self.map.get_planet(from)?, self.map.get_planet(to)?))
+    }
+
+    /// Returns the energy it costs the robot to reach every planet it knows a route to.
+    pub fn get_energy_costs(&self, robot: &Robot) -> HashMap<Uuid, u32> {
+        get_energy_costs_from(self.map, &self.rules.movement_cost, self.known_planets.as_ref(), robot.planet_id)
+    }
+
+    /// Moves the robot one planet along the cheapest known route, or regenerates if it lacks the energy to do so.
+    /// Returns None if the robot is already there or knows no route.
+    pub fn get_step_towards(&self, robot: &Robot, destination: Uuid) -> Option<Command> {
+        let path = find_cheapest_path(self.map, &self.rules.movement_cost, self.known_planets.as_ref(), robot.planet_id, destination)?;
+        let next_planet_id = *path.planets.get(1)?;
+        if robot.energy < self.get_movement_cost(&robot.planet_id, &next_planet_id)? {
+            return Some(self.command(CommandType::REGENERATE, robot.robot_id, None));
+        }
+        Some(self.command(CommandType::MOVEMENT, robot.robot_id, Some(next_planet_id)))
+    }
+
+    /// Whether the robot could mine or pick up something on the planet, as far as the bot knows.
+    pub fn can_mine(&self, robot: &Robot, planet_id: &Uuid) -> bool {
+        let Some(planet) = self.planets.get(planet_id) else { return false };
+        let can_mine_resources = planet.resources.as_ref()
+            .is_some_and(|(resource, amount)| *amount > 0 && robot.get_mineable_resources().contains(resource));
+        !robot.is_storage_full() && (can_mine_resources || !planet.loot.is_empty())
+    }
+
+    /// Returns the enemy robots the robot can attack, following `AttackRange`.
+    pub fn get_enemy_robots_in_range(&self, robot: &Robot) -> Vec<&'a Robot> {
+        let neighbours = match self.rules.attack_range {
+            AttackRange::SamePlanet => Vec::new(),
+            AttackRange::Adjacent => self.get_neighbours(&robot.planet_id),
+        };
+        self.enemy_robots.iter()
+            .filter(|enemy_robot| enemy_robot.planet_id == robot.planet_id || neighbours.contains(&enemy_robot.planet_id))
+            .copied()
+            .collect()
+    }
+}
+
+/// The energy an attack of the robot costs, see `validate_attack`.
+pub fn get_attack_energy_cost(robot: &Robot) -> u32 {
+    robot.levels.damage_level.get_int_value() + 1
+}
@@ -0,0 +1,2 @@
+pub mod bot_strategy;
+pub mod bot_view;
@@ -0,0 +1,277 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::game::game_rules::WinCondition;
+use crate::game::game_state::{GameState, RoundState};
+use crate::player::{PlayerState, PlayerStatus};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GameResults {
+    pub win_condition: WinCondition,
+    pub decided_in_round: u16,
+    pub winning_team: Option<String>,
+    pub standings: Vec<PlayerStanding>,
+    pub team_standings: Vec<TeamStanding>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerStanding {
+    pub rank: u16,
+    pub player_name: String,
+    pub team: String,
+    pub score: u64, // Score according to the win condition
+    pub score_breakdown: ScoreBreakdown,
+    pub status: PlayerStatus,
+    pub eliminated_in_round: Option<u16>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScoreBreakdown {
+    pub money: u32,
+    pub total_money_made: u32,
+    pub alive_robots: u32,
+    pub robot_value: u32,
+    pub kills: u32,
+    pub assists: u32,
+    pub planets_controlled: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TeamStanding {
+    pub rank: u16,
+    pub team: String,
+    pub score: u64, // Sum of the scores of the team's players
+    pub players: Vec<String>,
+}
+
+impl GameResults {
+    /// Ranks the players by the state of the current round.
+    pub fn from_game_state(game_state: &GameState) -> GameResults {
+        let round_state = &game_state.round_states[&game_state.current_round];
+        let win_condition = &game_state.rules.win_condition;
+        let controlled_planets = get_controlled_planets(game_state, round_state);
+
+        let mut standings: Vec<PlayerStanding> = game_state.participating_players.iter()
+            .filter_map(|player_name| round_state.player_name_player_map.get(player_name))
+            .map(|player| {
+                let team = game_state.get_team_of_player(&player.player_name).to_string();
+                let score_breakdown = get_score_breakdown(player, controlled_planets.get(&player.player_name).copied().unwrap_or(0));
+                let score = match win_condition {
+                    WinCondition::MostMoneyMade | WinCondition::MoneyTarget(_) => score_breakdown.total_money_made as u64,
+                    WinCondition::LastRobotsStanding => score_breakdown.alive_robots as u64,
+                    WinCondition::MostPlanetsControlled => score_breakdown.planets_controlled as u64,
+                };
+                PlayerStanding {
+                    rank: 0,
+                    player_name: player.player_name.clone(),
+                    team,
+                    score,
+                    score_breakdown,
+                    status: player.status.clone(),
+                    eliminated_in_round: player.eliminated_in_round,
+                }
+            })
+            .collect();
+        // Players who are still in the game rank above eliminated and forfeited ones, whatever their score.
+        // On equal score players who stayed in the game longer rank higher, then the money they made decides.
+        let sort_key = |standing: &PlayerStanding| (is_still_playing(&standing.status), standing.score, standing.eliminated_in_round.unwrap_or(u16::MAX), standing.score_breakdown.total_money_made);
+        standings.sort_by_key(|standing| Reverse(sort_key(standing)));
+        for index in 0..standings.len() {
+            standings[index].rank = if index > 0 && sort_key(&standings[index]) == sort_key(&standings[index - 1]) {
+                standings[index - 1].rank
+            } else {
+                index as u16 + 1
+            };
+        }
+
+        let mut team_standings: Vec<TeamStanding> = Vec::new();
+        for standing in &standings {
+            match team_standings.iter_mut().find(|team_standing| team_standing.team == standing.team) {
+                Some(team_standing) => {
+                    team_standing.score += standing.score;
+                    team_standing.players.push(standing.player_name.clone());
+                }
+                None => team_standings.push(TeamStanding {
+                    rank: 0,
+                    team: standing.team.clone(),
+                    score: standing.score,
+                    players: vec![standing.player_name.clone()],
+                }),
+            }
+        }
+        // Teams with a player who is still in the game are ahead of the others.
+        // On equal score the team with the better ranked best player is ahead, e.g. because it stayed in the game longer.
+        let best_player = |team: &str| standings.iter().find(|standing| standing.team == team);
+        let team_sort_key = |team_standing: &TeamStanding| {
+            let best_player = best_player(&team_standing.team);
+            (Reverse(best_player.is_some_and(|standing| is_still_playing(&standing.status))), Reverse(team_standing.score), best_player.map(|standing| standing.rank).unwrap_or(u16::MAX))
+        };
+        team_standings.sort_by_key(team_sort_key);
+        for index in 0..team_standings.len() {
+            team_standings[index].rank = if index > 0 && team_sort_key(&team_standings[index]) == team_sort_key(&team_standings[index - 1]) {
+                team_standings[index - 1].rank
+            } else {
+                index as u16 + 1
+            };
+        }
+        let winning_team = match team_standings.as_slice() {
+            [first, second, ..] if first.rank == second.rank => None,
+            [first, ..] => Some(first.team.clone()),
+            [] => None,
+        };
+
+        GameResults {
+            win_condition: win_condition.clone(),
+            decided_in_round: game_state.current_round,
+            winning_team,
+            standings,
+            team_standings,
+        }
+    }
+}
+
+fn is_still_playing(status: &PlayerStatus) -> bool {
+    matches!(status, PlayerStatus::Active | PlayerStatus::Disconnected)
+}
+
+fn get_score_breakdown(player: &PlayerState, planets_controlled: u32) -> ScoreBreakdown {
+    let alive_robots: Vec<_> = player.robots.values().filter(|robot| robot.is_alive()).collect();
+    ScoreBreakdown {
+        money: player.money.amount,
+        total_money_made: player.total_money_made.amount,
+        alive_robots: alive_robots.len() as u32,
+        robot_value: alive_robots.iter().map(|robot| robot.get_money_costs_for_robots_existing_upgrades()).sum(),
+        kills: player.killed_robots.values().map(|kills| kills.len() as u32).sum(),
+        assists: player.assisted_kills.values().map(|assists| assists.len() as u32).sum(),
+        planets_controlled,
+    }
+}
+
+/// Counts the planets per player, on which the player has alive robots and no other team has.
+fn get_controlled_planets(game_state: &GameState, round_state: &RoundState) -> HashMap<String, u32> {
+    let mut teams_per_planet: HashMap<Uuid, HashSet<&str>> = HashMap::new();
+    let mut players_per_planet: HashMap<Uuid, HashSet<&str>> = HashMap::new();
+    for player in round_state.player_name_player_map.values() {
+        for robot in player.robots.values().filter(|robot| robot.is_alive()) {
+            teams_per_planet.entry(robot.planet_id).or_default().insert(game_state.get_team_of_player(&player.player_name));
+            players_per_planet.entry(robot.planet_id).or_default().insert(&player.player_name);
+        }
+    }
+    let mut controlled_planets = HashMap::new();
+    for (planet_id, players) in &players_per_planet {
+        if teams_per_planet[planet_id].len() == 1 {
+            for player_name in players {
+                *controlled_planets.entry(player_name.to_string()).or_insert(0) += 1;
+            }
+        }
+    }
+    controlled_planets
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::game_rules::{ForfeitedRobots, GameRules};
+    use crate::game::game_state::GameStatus;
+    use crate::player::PlayerStatus;
+    use crate::planet::planet::Planet;
+    use crate::robot::robot::Robot;
+    use crate::test_support::create_game_state;
+
+    use super::*;
+
+    /// Creates a game of alice, bob and carol, who have no robots yet, on a single planet.
+    fn create_game_of_three_players(rules: GameRules) -> (GameState, Uuid) {
+        let planet = Planet::new(Uuid::new_v4(), 1);
+        let planet_id = planet.planet_id;
+        (create_game_state(vec![("alice", vec![]), ("bob", vec![]), ("carol", vec![])], vec![planet], rules), planet_id)
+    }
+
+    #[test]
+    fn test_standings_by_money_made() {
+        let (mut game_state, _) = create_game_of_three_players(GameRules::default());
+        game_state.get_player_for_current_round_as_mut("bob").unwrap().total_money_made.amount = 900;
+        game_state.get_player_for_current_round_as_mut("carol").unwrap().total_money_made.amount = 900;
+
+        let results = GameResults::from_game_state(&game_state);
+        let ranks: Vec<(&str, u16)> = results.standings.iter().map(|standing| (standing.player_name.as_str(), standing.rank)).collect();
+        assert_eq!(ranks, vec![("bob", 1), ("carol", 1), ("alice", 3)]);
+        assert_eq!(results.winning_team, None);
+    }
+
+    #[test]
+    fn test_money_target_ends_the_game() {
+        let (mut game_state, planet_id) = create_game_of_three_players(GameRules { win_condition: WinCondition::MoneyTarget(1000), ..GameRules::default() });
+        for player_name in ["alice", "bob", "carol"] {
+            let robot = Robot::new(Uuid::new_v4(), planet_id);
+            game_state.get_player_for_current_round_as_mut(player_name).unwrap().robots.insert(robot.robot_id, robot);
+        }
+        game_state.end_game_if_decided();
+        assert_eq!(game_state.status, GameStatus::Created);
+
+        game_state.get_player_for_current_round_as_mut("carol").unwrap().total_money_made.amount = 1000;
+        game_state.end_game_if_decided();
+        assert_eq!(game_state.status, GameStatus::Ended);
+        assert_eq!(game_state.results.unwrap().winning_team, Some("carol".to_string()));
+    }
+
+    #[test]
+    fn test_last_robots_standing_ranks_by_elimination_round() {
+        let (mut game_state, planet_id) = create_game_of_three_players(GameRules { win_condition: WinCondition::LastRobotsStanding, ..GameRules::default() });
+        game_state.get_player_for_current_round_as_mut("alice").unwrap().money.amount = 0;
+        let robot = Robot::new(Uuid::new_v4(), planet_id);
+        game_state.get_player_for_current_round_as_mut("bob").unwrap().robots.insert(robot.robot_id, robot);
+        game_state.end_game_if_decided();
+        assert_eq!(game_state.get_player_for_round("alice", 0).unwrap().status, PlayerStatus::Eliminated);
+        assert_eq!(game_state.status, GameStatus::Created);
+        game_state.start_next_round();
+        let round_state = game_state.round_states[&0].clone();
+        game_state.round_states.insert(1, round_state);
+        game_state.get_player_for_current_round_as_mut("carol").unwrap().money.amount = 0;
+        game_state.end_game_if_decided();
+
+        let results = game_state.results.unwrap();
+        let standings: Vec<(&str, Option<u16>)> = results.standings.iter().map(|standing| (standing.player_name.as_str(), standing.eliminated_in_round)).collect();
+        assert_eq!(standings, vec![("bob", None), ("carol", Some(1)), ("alice", Some(0))]);
+        assert_eq!(results.decided_in_round, 1);
+    }
+
+    #[test]
+    fn test_forfeited_players_no_longer_count_as_playing() {
+        let (mut game_state, planet_id) = create_game_of_three_players(GameRules { forfeited_robots: ForfeitedRobots::Removed, ..GameRules::default() });
+        game_state.remove_player("carol");
+        assert_eq!(game_state.participating_players, vec!["alice", "bob"]);
+        for player_name in ["alice", "bob"] {
+            let robot = Robot::new(Uuid::new_v4(), planet_id);
+            game_state.get_player_for_current_round_as_mut(player_name).unwrap().robots.insert(robot.robot_id, robot);
+        }
+
+        game_state.forfeit_player("alice");
+        let alice = game_state.get_player_for_round("alice", 0).unwrap();
+        assert_eq!(alice.status, PlayerStatus::Forfeited);
+        assert!(alice.robots.is_empty());
+        game_state.end_game_if_decided();
+        assert_eq!(game_state.status, GameStatus::Ended);
+        assert_eq!(game_state.results.unwrap().winning_team, Some("bob".to_string()));
+    }
+
+    #[test]
+    fn test_forfeited_players_rank_below_players_still_in_the_game() {
+        let (mut game_state, planet_id) = create_game_of_three_players(GameRules::default());
+        for player_name in ["alice", "bob", "carol"] {
+            let robot = Robot::new(Uuid::new_v4(), planet_id);
+            game_state.get_player_for_current_round_as_mut(player_name).unwrap().robots.insert(robot.robot_id, robot);
+        }
+        game_state.get_player_for_current_round_as_mut("alice").unwrap().total_money_made.amount = 5000;
+        game_state.get_player_for_current_round_as_mut("bob").unwrap().total_money_made.amount = 900;
+        game_state.get_player_for_current_round_as_mut("carol").unwrap().total_money_made.amount = 700;
+        game_state.forfeit_player("alice");
+
+        let results = GameResults::from_game_state(&game_state);
+        let ranks: Vec<(&str, u16)> = results.standings.iter().map(|standing| (standing.player_name.as_str(), standing.rank)).collect();
+        assert_eq!(ranks, vec![("bob", 1), ("carol", 2), ("alice", 3)]);
+        assert_eq!(results.winning_team, Some("bob".to_string()));
+    }
+}
@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+use crate::planet::planet::Planet;
+
+/// Optional rules which can be chosen when a game is created.
+/// Every rule has a default, so games created without rules (or stored before a rule existed) keep working.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct GameRules {
+    pub player_order: PlayerOrder,
+    pub contested_mining: ContestedMining,
+    pub attack_range: AttackRange,
+    pub movement_cost: MovementCostModel,
+    pub friendly_fire: FriendlyFire,
+    /// When a robot dies, its inventory is dropped on its planet, where any robot can pick it up.
+    pub drop_loot: bool,
+    /// Money paid to the player who gets the kill.
+    pub kill_bounty: u32,
+    pub win_condition: WinCondition,
+    pub visibility: Visibility,
+    pub forfeited_robots: ForfeitedRobots,
+    pub turn_gate: TurnGate,
+    pub submission_mode: SubmissionMode,
+}
+
+/// Order in which the commands of the players are resolved within a round.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum PlayerOrder {
+    /// The player who goes first changes every round, following the join order.
+    #[default]
+    RotatingPriority,
+    /// The order is shuffled every round with a generator seeded by the game seed and the round number.
+    SeededRandom,
+}
+
+/// How a planet's resources are split, when more is mined than the planet has left.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum ContestedMining {
+    /// Robots are served one after another in the player order of the round.
+    #[default]
+    InPlayerOrder,
+    /// Every robot gets a share proportional to the amount it wanted to mine.
+    Proportional,
+}
+
+/// Where a target has to be, so that a robot can attack it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum AttackRange {
+    #[default]
+    SamePlanet,
+    /// The target may be on the same planet or on one of its neighbours.
+    Adjacent,
+}
+
+/// Which planet's movement difficulty a robot pays in energy, when it moves to a neighbour.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum MovementCostModel {
+    /// The planet which is left.
+    #[default]
+    Source,
+    /// The planet which is entered.
+    Destination,
+    /// The more difficult of both planets.
+    Max,
+}
+
+impl MovementCostModel {
+    pub fn get_movement_cost(&self, from: &Planet, to: &Planet) -> u32 {
+        let movement_difficulty = match self {
+            MovementCostModel::Source => from.movement_difficulty,
+            MovementCostModel::Destination => to.movement_difficulty,
+            MovementCostModel::Max => std::cmp::max(from.movement_difficulty, to.movement_difficulty),
+        };
+        movement_difficulty as u32
+    }
+}
+
+/// Whether robots may attack other robots of their own player or of their teammates.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum FriendlyFire {
+    #[default]
+    Off,
+    On,
+    /// Friendly robots take damage, but killing one is not recorded as a kill or an assist.
+    OnWithoutKillCredit,
+}
+
+/// Decides how players are ranked when the game ends.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum WinCondition {
+    /// The player who made the most money over the whole game wins.
+    #[default]
+    MostMoneyMade,
+    /// The player with the most alive robots wins, players who were eliminated later rank higher.
+    LastRobotsStanding,
+    /// The game ends as soon as a player made this amount of money.
+    MoneyTarget(u32),
+    /// The player who controls the most planets wins. A planet is controlled by the players of the only team with alive robots on it.
+    MostPlanetsControlled,
+}
+
+/// What players can see of the map and of the robots of other players.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum Visibility {
+    /// Players see the whole map and all robots.
+    #[default]
+    Full,
+    /// Players only see planets within `sight_radius` moves of their own or allied robots. Planets which were in sight
+    /// before are shown as they were last seen, all other planets are hidden.
+    Fog { sight_radius: u8 },
+}
+
+/// What happens to the robots of a player who forfeits a running game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum ForfeitedRobots {
+    /// The robots stay on the map without doing anything and can still be attacked.
+    #[default]
+    Inert,
+    /// The robots are taken off the map.
+    Removed,
+}
+
+/// Decides when a round is processed. Robots without a command simply do nothing in that round.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum TurnGate {
+    /// Every active player submitted a command for each of their alive robots, or a buying command if they have none.
+    #[default]
+    AllRobotsCovered,
+    /// Every active player submitted at least one batch of commands.
+    OneBatchPerPlayer,
+    /// Every active player sent an `END_TURN` command.
+    EndTurnMarker,
+    /// Every active player submitted at least one batch, or the given number of seconds passed since the round started.
+    /// Players who did not submit anything in time become disconnected.
+    Timeout { seconds: u64 },
+}
+
+/// What happens when a player submits more than one batch of commands in a round.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum SubmissionMode {
+    /// A new batch replaces all commands the player queued before.
+    #[default]
+    Replace,
+    /// A new batch replaces only the queued commands of the robots it contains. The player has to call `ready`
+    /// (or send `END_TURN`) before the round can be processed.
+    Merge,
+}
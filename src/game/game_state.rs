@@ -1,13 +1,21 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use tracing::log::info;
+use tracing::log::{debug, info};
 use uuid::Uuid;
 
+use crate::game::game_results::GameResults;
+use crate::game::game_rules::{ForfeitedRobots, GameRules, PlayerOrder, Visibility, WinCondition};
 use crate::planet::planet::Planet;
-use crate::player::PlayerState;
+use crate::planet::planet_memory::PlanetMemory;
+use crate::player::{PlayerState, PlayerStatus};
+use crate::robot::enemy_intel::EnemyIntel;
 use crate::robot::robot::Robot;
+use crate::trading::external::handler::buy_command_handler::Item;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GameState {
@@ -18,6 +26,16 @@ pub struct GameState {
     pub max_rounds: u16,
     pub max_players: u8,
     pub round_states: HashMap<u16, RoundState>,
+    #[serde(default)]
+    pub rules: GameRules,
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub teams: HashMap<String, String>, // PlayerName -> TeamName, players without a team play on their own
+    #[serde(default)]
+    pub results: Option<GameResults>, // Final standings, set once the game has ended
+    #[serde(default)]
+    pub round_started_at: Option<u64>, // Unix timestamp in milliseconds, at which the current round started
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -39,6 +57,8 @@ pub struct RoundState {
 pub struct GameMap {
     pub planets: Vec<Vec<Option<Planet>>>,
     pub indices: HashMap<Uuid, (usize, usize)>,
+    #[serde(default)]
+    pub spawn_points: Vec<Uuid>, // Planets on which bought robots are spawned, any planet if empty
 }
 
 impl GameMap {
@@ -54,6 +74,7 @@ impl GameMap {
         GameMap {
             planets,
             indices,
+            spawn_points: Vec::new(),
         }
     }
 
@@ -70,10 +91,29 @@ impl GameMap {
         }
         None
     }
+
+    /// Returns the given planets and all planets which can be reached from them in at most `distance` moves.
+    pub fn get_planets_within_distance(&self, planet_ids: impl IntoIterator<Item=Uuid>, distance: u8) -> HashSet<Uuid> {
+        let mut planets_within_distance: HashSet<Uuid> = planet_ids.into_iter().collect();
+        let mut frontier: Vec<Uuid> = planets_within_distance.iter().copied().collect();
+        for _ in 0..distance {
+            let mut next_frontier = Vec::new();
+            for planet_id in frontier {
+                let Some(planet) = self.get_planet(&planet_id) else { continue };
+                for neighbour_id in planet.neighbours.values() {
+                    if planets_within_distance.insert(*neighbour_id) {
+                        next_frontier.push(*neighbour_id);
+                    }
+                }
+            }
+            frontier = next_frontier;
+        }
+        planets_within_distance
+    }
 }
 
 impl GameState {
-    pub fn new(game_id: Uuid, max_rounds: u16, max_players: u8, planets: Vec<Vec<Option<Planet>>>) -> GameState {
+    pub fn new(game_id: Uuid, max_rounds: u16, max_players: u8, planets: Vec<Vec<Option<Planet>>>, rules: GameRules, seed: u64) -> GameState {
         let initial_round = RoundState {
             round_number: 0,
             player_name_player_map: HashMap::new(),
@@ -89,9 +129,132 @@ impl GameState {
             current_round: 0,
             max_rounds,
             max_players: max_players,
+            rules,
+            seed,
+            teams: HashMap::new(),
+            results: None,
+            round_started_at: None,
         }
     }
 
+    /// Returns the team of a player. A player who joined without a team forms a team of their own, named after them.
+    pub fn get_team_of_player<'a>(&'a self, player_name: &'a str) -> &'a str {
+        self.teams.get(player_name).map(|team_name| team_name.as_str()).unwrap_or(player_name)
+    }
+
+    pub fn are_allies(&self, player_name: &str, other_player_name: &str) -> bool {
+        self.get_team_of_player(player_name) == self.get_team_of_player(other_player_name)
+    }
+
+    /// Whether a player who joins with this name and team would become an ally of somebody without both of them
+    /// asking for it, because a team name is also the name of a player who plays on their own.
+    pub fn is_team_name_taken(&self, player_name: &str, team: Option<&str>) -> bool {
+        let team_name = team.unwrap_or(player_name);
+        self.participating_players.iter().any(|other_player_name| other_player_name != player_name
+            && self.get_team_of_player(other_player_name) == team_name
+            && (team.is_none() || !self.teams.contains_key(other_player_name)))
+    }
+
+    pub fn get_teammates(&self, player_name: &str) -> Vec<&String> {
+        self.participating_players.iter()
+            .filter(|other_player_name| other_player_name.as_str() != player_name && self.are_allies(player_name, other_player_name))
+            .collect()
+    }
+
+    /// Returns the planets visited by the player or one of their teammates up to the given round.
+    pub fn get_known_planets(&self, player_name: &str, round_number: u16) -> HashSet<Uuid> {
+        let mut known_planets = HashSet::new();
+        for ally_name in self.get_teammates(player_name).into_iter().map(|teammate| teammate.as_str()).chain([player_name]) {
+            if let Some(player) = self.get_player_for_round(ally_name, round_number) {
+                known_planets.extend(player.visited_planets.iter());
+            }
+        }
+        known_planets
+    }
+
+    /// Returns the planets the player can currently see in the given round, or None if they can see the whole map.
+    pub fn get_visible_planets(&self, player_name: &str, round_number: u16) -> Option<HashSet<Uuid>> {
+        let Visibility::Fog { sight_radius } = self.rules.visibility else { return None };
+        let Some(round_state) = self.round_states.get(&round_number) else { return Some(HashSet::new()) };
+        let robot_planets = round_state.player_name_player_map.values()
+            .filter(|player| self.are_allies(&player.player_name, player_name))
+            .flat_map(|player| player.robots.values())
+            .filter(|robot| robot.is_alive())
+            .map(|robot| robot.planet_id);
+        Some(round_state.map.get_planets_within_distance(robot_planets, sight_radius))
+    }
+
+    /// Remembers what every player currently sees, so it can be shown once the planets are out of sight again.
+    pub fn update_planet_memories(&mut self) {
+        let current_round = self.current_round;
+        let visible_planets_per_player: Vec<(String, HashSet<Uuid>)> = self.participating_players.iter()
+            .filter_map(|player_name| self.get_visible_planets(player_name, current_round).map(|visible_planets| (player_name.clone(), visible_planets)))
+            .collect();
+        let Some(round_state) = self.round_states.get_mut(&current_round) else { return };
+        for (player_name, visible_planets) in visible_planets_per_player {
+            let Some(player) = round_state.player_name_player_map.get_mut(&player_name) else { continue };
+            for planet_id in visible_planets {
+                if let Some(planet) = round_state.map.get_planet(&planet_id) {
+                    player.planet_memories.insert(planet_id, PlanetMemory::new(planet, current_round));
+                }
+            }
+        }
+    }
+
+    /// Records every enemy robot which is in sight of a player's or an allied robot, and forgets the ones seen dead.
+    /// With full visibility all enemy robots are in sight.
+    pub fn update_enemy_intel(&mut self) {
+        let current_round = self.current_round;
+        let mut sightings: Vec<(String, Vec<(String, Robot)>)> = Vec::new();
+        for player_name in &self.participating_players {
+            let visible_planets = self.get_visible_planets(player_name, current_round);
+            let sighted_robots = self.round_states[&current_round].player_name_player_map.values()
+                .filter(|enemy| !self.are_allies(&enemy.player_name, player_name))
+                .flat_map(|enemy| enemy.robots.values().map(|robot| (enemy.player_name.clone(), robot.clone())))
+                .filter(|(_, robot)| visible_planets.as_ref().is_none_or(|visible_planets| visible_planets.contains(&robot.planet_id)))
+                .collect();
+            sightings.push((player_name.clone(), sighted_robots));
+        }
+        let Some(round_state) = self.round_states.get_mut(&current_round) else { return };
+        for (player_name, sighted_robots) in sightings {
+            let Some(player) = round_state.player_name_player_map.get_mut(&player_name) else { continue };
+            for (enemy_player_name, robot) in sighted_robots {
+                if robot.is_alive() {
+                    player.enemy_intel.insert(robot.robot_id, EnemyIntel::new(&enemy_player_name, &robot, current_round));
+                } else {
+                    player.enemy_intel.remove(&robot.robot_id);
+                }
+            }
+        }
+    }
+
+    /// Returns the names of the players in the order their commands are resolved in the current round.
+    /// Handlers which let players compete for something (spawn planets, resources, ...) should iterate in this order
+    /// instead of the order of `player_name_player_map`, which depends on the hashing of the player names.
+    pub fn get_player_order_for_current_round(&self) -> Vec<String> {
+        let mut player_order: Vec<String> = match self.round_states.get(&self.current_round) {
+            Some(round_state) => self.participating_players.iter()
+                .filter(|player_name| round_state.player_name_player_map.contains_key(*player_name))
+                .cloned()
+                .collect(),
+            None => return Vec::new(),
+        };
+        if player_order.is_empty() {
+            return player_order;
+        }
+        match self.rules.player_order {
+            PlayerOrder::RotatingPriority => {
+                let offset = self.current_round as usize % player_order.len();
+                player_order.rotate_left(offset);
+            }
+            PlayerOrder::SeededRandom => {
+                let mut rng = StdRng::seed_from_u64(self.seed ^ (self.current_round as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+                player_order.shuffle(&mut rng);
+            }
+        }
+        player_order
+    }
+
     pub fn get_player_name_by_robot_id(&self, robot_id: &Uuid) -> Option<&String> {
         if let Some(round_state) = self.round_states.get(&self.current_round) {
             for player in round_state.player_name_player_map.values() {
@@ -125,7 +288,7 @@ impl GameState {
     pub fn get_enemy_robots_for_current_round(&self, own_player_name: &str) -> Option<Vec<&Robot>> {
         if let Some(round_state) = self.round_states.get(&self.current_round) {
             let enemy_robots: Vec<&Robot> = round_state.player_name_player_map.values()
-                .filter(|player| player.player_name != own_player_name)
+                .filter(|player| !self.are_allies(&player.player_name, own_player_name))
                 .flat_map(|player| player.robots.values())
                 .collect();
             Some(enemy_robots)
@@ -134,6 +297,13 @@ impl GameState {
         }
     }
 
+    pub fn get_allied_robots_for_current_round(&self, own_player_name: &str) -> Option<Vec<&Robot>> {
+        let round_state = self.round_states.get(&self.current_round)?;
+        Some(round_state.player_name_player_map.values()
+            .filter(|player| player.player_name != own_player_name && self.are_allies(&player.player_name, own_player_name))
+            .flat_map(|player| player.robots.values())
+            .collect())
+    }
 
     pub fn get_robots_for_current_round_by_robot_id(&mut self, robot_id: &Uuid) -> Option<&mut HashMap<Uuid, Robot>> {
         if let Some(round_state) = self.round_states.get_mut(&self.current_round) {
@@ -212,4 +382,195 @@ impl GameState {
         }
         false
     }
+
+    /// Adds a player who joins before the game has started, `team` is the team they play in.
+    pub fn add_player(&mut self, player: PlayerState, team: Option<String>) {
+        let player_name = player.player_name.clone();
+        self.participating_players.push(player_name.clone());
+        if let Some(team) = team {
+            self.teams.insert(player_name.clone(), team);
+        }
+        if let Some(round_state) = self.round_states.get_mut(&0) {
+            round_state.player_name_player_map.insert(player_name.clone(), player);
+        }
+        self.assign_home_planet(&player_name);
+    }
+
+    /// Gives a player who joins the first spawn point of the map which is not the home planet of another player yet.
+    pub fn assign_home_planet(&mut self, player_name: &str) {
+        let Some(round_state) = self.round_states.get_mut(&0) else { return };
+        let taken_home_planets: HashSet<Uuid> = round_state.player_name_player_map.values().filter_map(|player| player.home_planet).collect();
+        let free_spawn_point = round_state.map.spawn_points.iter().find(|spawn_point| !taken_home_planets.contains(spawn_point)).copied();
+        if let Some(player) = round_state.player_name_player_map.get_mut(player_name) {
+            player.home_planet = free_spawn_point;
+        }
+    }
+
+    /// Removes a player who leaves the game before it has started.
+    pub fn remove_player(&mut self, player_name: &str) {
+        self.participating_players.retain(|participating_player| participating_player != player_name);
+        self.teams.remove(player_name);
+        if let Some(round_state) = self.round_states.get_mut(&0) {
+            round_state.player_name_player_map.remove(player_name);
+        }
+    }
+
+    /// Lets a player give up a running game. They stay in the game as a spectator, their queued commands are dropped
+    /// and their robots are handled according to `ForfeitedRobots`.
+    pub fn forfeit_player(&mut self, player_name: &str) {
+        let current_round = self.current_round;
+        let forfeited_robots = self.rules.forfeited_robots.clone();
+        if let Some(player) = self.get_player_for_current_round_as_mut(player_name) {
+            player.status = PlayerStatus::Forfeited;
+            player.eliminated_in_round = Some(current_round);
+            player.commands.values_mut().for_each(|commands| commands.clear());
+            if forfeited_robots == ForfeitedRobots::Removed {
+                player.robots.clear();
+            }
+        }
+    }
+
+    /// Called after a round was processed. Records the players who lost in this round and ends the game,
+    /// when the last round was reached, only one team is left or the win condition is already decided.
+    pub fn end_game_if_decided(&mut self) {
+        let current_highest_round = self.current_round;
+        let round_state = self.round_states.get(&current_highest_round).unwrap();
+        let mut teams = HashSet::new();
+        let mut teams_still_playing = HashSet::new();
+        let mut eliminated_players = Vec::new();
+        let mut money_target_reached = false;
+
+        for player in round_state.player_name_player_map.values() {
+            let team = self.get_team_of_player(&player.player_name);
+            teams.insert(team);
+            // Wenn der Spieler keine ALIVE Robots mehr hat und kein Geld sich neue zu kaufen, dann kann er sich keine Roboter leisten
+            let alive_robots_exist = player.robots.values().any(|robot| robot.is_alive());
+            if !alive_robots_exist && player.money.amount < Item::Robot(1).get_cost() {
+                debug!("Player {} has no robots and can't afford to buy a new robot. HE LOST!", player.player_name);
+                if player.status == PlayerStatus::Active || player.status == PlayerStatus::Disconnected {
+                    eliminated_players.push(player.player_name.clone());
+                }
+            } else if player.status != PlayerStatus::Forfeited {
+                teams_still_playing.insert(team);
+            }
+            if let WinCondition::MoneyTarget(money_target) = self.rules.win_condition {
+                money_target_reached |= player.total_money_made.amount >= money_target;
+            }
+        }
+        let only_one_team_left = (teams.len() > 1 && teams_still_playing.len() <= 1) || teams_still_playing.is_empty();
+
+        for player_name in eliminated_players {
+            if let Some(player) = self.get_player_for_current_round_as_mut(&player_name) {
+                player.status = PlayerStatus::Eliminated;
+                player.eliminated_in_round = Some(current_highest_round);
+            }
+        }
+        // Setze current_round auf max_rounds, wenn alle Teams bis auf maximal eines sich keine Roboter leisten können
+        if only_one_team_left || money_target_reached || self.current_round == self.max_rounds {
+            self.results = Some(GameResults::from_game_state(self));
+            self.status = GameStatus::Ended;
+            self.current_round = self.max_rounds;
+            if !self.round_states.contains_key(&self.current_round) {
+                let round_state = self.round_states.get(&current_highest_round).unwrap();
+                self.round_states.insert(self.current_round, round_state.clone());
+            }
+            info!("Game {} was decided in round {}", self.game_id, current_highest_round);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::planet::direction::Direction;
+    use crate::planet::resource::Resource;
+    use crate::test_support::{create_game_state, create_robots};
+
+    use super::*;
+
+    #[test]
+    fn test_fog_of_war_remembers_planets_out_of_sight() {
+        let mut planets: Vec<Planet> = (0..4).map(|_| Planet::new(Uuid::new_v4(), 1)).collect();
+        let ids: Vec<Uuid> = planets.iter().map(|planet| planet.planet_id).collect();
+        for index in 0..3 {
+            planets[index].set_neighbour(Direction::EAST, ids[index + 1]);
+            planets[index + 1].set_neighbour(Direction::WEST, ids[index]);
+        }
+        planets[1].resources = Some((Resource::COAL, 10));
+        let rules = GameRules { visibility: Visibility::Fog { sight_radius: 1 }, ..GameRules::default() };
+        let robot = Robot::new(Uuid::new_v4(), ids[0]);
+        let robot_id = robot.robot_id;
+        let mut game_state = create_game_state(vec![("alice", vec![robot])], planets, rules);
+
+        assert_eq!(game_state.get_visible_planets("alice", 0), Some(HashSet::from([ids[0], ids[1]])));
+        game_state.update_planet_memories();
+
+        // The robot moves out of sight of planet b, which is mined by somebody else in the meantime
+        game_state.get_robots_for_current_round("alice").unwrap().get_mut(&robot_id).unwrap().planet_id = ids[3];
+        game_state.round_states.get_mut(&0).unwrap().map.get_planet_as_mut(&ids[1]).unwrap().resources = Some((Resource::COAL, 2));
+        assert_eq!(game_state.get_visible_planets("alice", 0), Some(HashSet::from([ids[2], ids[3]])));
+        let planet_memories = &game_state.get_player_for_round("alice", 0).unwrap().planet_memories;
+        assert_eq!(planet_memories[&ids[1]].resources, Some((Resource::COAL, 10)));
+        assert!(!planet_memories.contains_key(&ids[2]));
+    }
+
+    #[test]
+    fn test_teams() {
+        let planet = Planet::new(Uuid::new_v4(), 1);
+        let planet_id = planet.planet_id;
+        let mut game_state = create_game_state(vec![], vec![planet], GameRules::default());
+        for (player_name, team) in [("alice", None), ("bob", Some("red")), ("carol", Some("red"))] {
+            assert!(!game_state.is_team_name_taken(player_name, team));
+            let mut player = PlayerState::new(player_name.to_string(), 500);
+            player.robots = create_robots(1, planet_id).into_iter().map(|robot| (robot.robot_id, robot)).collect();
+            game_state.add_player(player, team.map(str::to_string));
+        }
+        assert!(game_state.are_allies("bob", "carol"));
+        assert!(!game_state.are_allies("alice", "bob"));
+        assert_eq!(game_state.get_teammates("bob"), vec!["carol"]);
+        assert!(game_state.get_teammates("alice").is_empty());
+        // Nobody becomes an ally of alice or of team red by accident
+        assert!(game_state.is_team_name_taken("dave", Some("alice")));
+        assert!(game_state.is_team_name_taken("red", None));
+        assert!(!game_state.is_team_name_taken("dave", Some("red")));
+
+        game_state.end_game_if_decided();
+        assert_eq!(game_state.status, GameStatus::Created);
+        game_state.get_robots_for_current_round("alice").unwrap().values_mut().for_each(|robot| robot.health = 0);
+        game_state.get_player_for_current_round_as_mut("alice").unwrap().money.amount = 0;
+        // Team red is left, even though it has two players
+        game_state.end_game_if_decided();
+        assert_eq!(game_state.status, GameStatus::Ended);
+        assert_eq!(game_state.results.as_ref().unwrap().winning_team, Some("red".to_string()));
+    }
+
+    #[test]
+    fn test_enemy_intel_keeps_last_sighting() {
+        let mut planets: Vec<Planet> = (0..3).map(|_| Planet::new(Uuid::new_v4(), 1)).collect();
+        let ids: Vec<Uuid> = planets.iter().map(|planet| planet.planet_id).collect();
+        for index in 0..2 {
+            planets[index].set_neighbour(Direction::EAST, ids[index + 1]);
+            planets[index + 1].set_neighbour(Direction::WEST, ids[index]);
+        }
+        let rules = GameRules { visibility: Visibility::Fog { sight_radius: 0 }, ..GameRules::default() };
+        let robots = create_robots(2, ids[0]);
+        let robot_ids: Vec<Uuid> = robots.iter().map(|robot| robot.robot_id).collect();
+        let mut game_state = create_game_state(vec![("alice", vec![robots[0].clone()]), ("bob", vec![robots[1].clone()])], planets, rules);
+        game_state.update_enemy_intel();
+        assert_eq!(game_state.get_player_for_round("alice", 0).unwrap().enemy_intel[&robot_ids[1]].planet_id, ids[0]);
+
+        // Bob's robot leaves, alice still remembers where it was
+        game_state.get_robots_for_current_round("bob").unwrap().get_mut(&robot_ids[1]).unwrap().planet_id = ids[2];
+        game_state.current_round = 1;
+        let round_state = game_state.round_states[&0].clone();
+        game_state.round_states.insert(1, round_state);
+        game_state.update_enemy_intel();
+        let enemy_intel = &game_state.get_player_for_round("alice", 1).unwrap().enemy_intel[&robot_ids[1]];
+        assert_eq!((enemy_intel.planet_id, enemy_intel.last_seen_round), (ids[0], 0));
+
+        // Alice sees the robot dying
+        game_state.get_robots_for_current_round("bob").unwrap().get_mut(&robot_ids[1]).unwrap().planet_id = ids[0];
+        game_state.get_robots_for_current_round("bob").unwrap().get_mut(&robot_ids[1]).unwrap().health = 0;
+        game_state.update_enemy_intel();
+        assert!(game_state.get_player_for_round("alice", 1).unwrap().enemy_intel.is_empty());
+    }
 }
@@ -0,0 +1,280 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::sync::{Arc, OnceLock};
+
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg;
+use uuid::Uuid;
+
+use crate::game::game_state::GameState;
+use crate::game::render_error::RenderError;
+use crate::planet::direction::Direction;
+use crate::planet::planet::Planet;
+use crate::planet::resource::Resource;
+
+const CELL_SIZE: f32 = 48.0;
+const PLANET_RADIUS: f32 = 15.0;
+const MARGIN: f32 = 24.0;
+const LEGEND_HEIGHT: f32 = 28.0;
+/// Longest side of a rasterized map in pixels. Larger maps are scaled down, so that a map of size 255 doesn't take
+/// hundreds of megabytes.
+pub const MAX_IMAGE_SIZE: f32 = 2048.0;
+const PLAYER_COLOURS: [&str; 8] = ["#e6194b", "#3cb44b", "#4363d8", "#f58231", "#911eb4", "#42d4f4", "#f032e6", "#bfef45"];
+
+/// How a planet is drawn from the perspective of the player the map is rendered for.
+enum PlanetView<'a> {
+    Visible,
+    Remembered(Option<&'a (Resource, u32)>), // What the player saw on the planet the last time
+    Unknown,
+}
+
+/// Draws the map of a round as SVG: planets coloured by resource with their difficulty ring as outline, connections,
+/// obstacles and the number of alive robots of every player on each planet.
+/// With a player, the map is drawn as they see it. Planets out of sight are faded and show what the player remembers,
+/// planets they have never seen are drawn as unknown, and enemy robots are only drawn on planets in sight.
+/// Returns None if the round or the player doesn't exist.
+pub fn render_svg(game_state: &GameState, round_number: u16, player_name: Option<&str>) -> Option<String> {
+    render(game_state, round_number, player_name, false)
+}
+
+/// Draws the map like `render_svg`, together with what happened since the previous round: robot moves as arrows in the
+/// colour of their player, planets with battles in a red ring and mined deposits in a yellow ring.
+pub fn render_replay_frame_svg(game_state: &GameState, round_number: u16, player_name: Option<&str>) -> Option<String> {
+    render(game_state, round_number, player_name, true)
+}
+
+fn render(game_state: &GameState, round_number: u16, player_name: Option<&str>, show_round_events: bool) -> Option<String> {
+    let round_state = game_state.round_states.get(&round_number)?;
+    if let Some(player_name) = player_name {
+        round_state.player_name_player_map.get(player_name)?;
+    }
+    let planets = &round_state.map.planets;
+    let columns = planets.len();
+    let rows = planets.iter().map(|column| column.len()).max().unwrap_or(0);
+    // Hex maps are drawn with every other column shifted down by half a planet
+    let is_hex = planets.iter().flatten().flatten()
+        .any(|planet| planet.neighbours.keys().any(|direction| matches!(direction, Direction::NORTHEAST | Direction::SOUTHEAST | Direction::SOUTHWEST | Direction::NORTHWEST)));
+    let center = |x: usize, y: usize| -> (f32, f32) {
+        let shift = if is_hex && x % 2 == 1 { CELL_SIZE / 2.0 } else { 0.0 };
+        (MARGIN + CELL_SIZE * (x as f32 + 0.5), MARGIN + CELL_SIZE * (y as f32 + 0.5) + shift)
+    };
+
+    let visible_planets = player_name.and_then(|player_name| game_state.get_visible_planets(player_name, round_number));
+    let is_visible = |planet_id: &Uuid| visible_planets.as_ref().is_none_or(|visible_planets| visible_planets.contains(planet_id));
+    let viewer = player_name.and_then(|player_name| round_state.player_name_player_map.get(player_name));
+    let get_view = |planet: &Planet| {
+        if is_visible(&planet.planet_id) {
+            return PlanetView::Visible;
+        }
+        match viewer.and_then(|viewer| viewer.planet_memories.get(&planet.planet_id)) {
+            Some(planet_memory) => PlanetView::Remembered(planet_memory.resources.as_ref()),
+            None => PlanetView::Unknown,
+        }
+    };
+
+    let width = MARGIN * 2.0 + CELL_SIZE * columns as f32;
+    let height = MARGIN * 2.0 + CELL_SIZE * (rows as f32 + if is_hex { 0.5 } else { 0.0 }) + LEGEND_HEIGHT;
+    let mut svg = String::new();
+    let _ = write!(svg, r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif">"##);
+    svg.push_str("<defs>");
+    for (player_index, colour) in PLAYER_COLOURS.iter().enumerate() {
+        let _ = write!(svg, r##"<marker id="arrow-{player_index}" viewBox="0 0 10 10" refX="9" refY="5" markerWidth="5" markerHeight="5" orient="auto"><path d="M0,0 L10,5 L0,10 z" fill="{colour}"/></marker>"##);
+    }
+    svg.push_str("</defs>");
+    let _ = write!(svg, r##"<rect width="100%" height="100%" fill="#0b1021"/>"##);
+
+    // Connections, each drawn once. Wrap-around connections of torus maps would cross the whole map and are left out.
+    let mut drawn_connections: HashSet<(Uuid, Uuid)> = HashSet::new();
+    for (planet_id, &(x, y)) in &round_state.map.indices {
+        let planet = planets[x][y].as_ref().unwrap();
+        if matches!(get_view(planet), PlanetView::Unknown) {
+            continue;
+        }
+        for neighbour_id in planet.neighbours.values() {
+            let Some(&(neighbour_x, neighbour_y)) = round_state.map.indices.get(neighbour_id) else { continue };
+            if x.abs_diff(neighbour_x) > 1 || y.abs_diff(neighbour_y) > 1 || !drawn_connections.insert((*planet_id.min(neighbour_id), *planet_id.max(neighbour_id))) {
+                continue;
+            }
+            let ((x1, y1), (x2, y2)) = (center(x, y), center(neighbour_x, neighbour_y));
+            let _ = write!(svg, r##"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="#3b4a6b" stroke-width="3"/>"##);
+        }
+    }
+
+    for (x, column) in planets.iter().enumerate() {
+        for (y, planet) in column.iter().enumerate() {
+            let (cx, cy) = center(x, y);
+            let Some(planet) = planet else {
+                let (left, top, size) = (cx - PLANET_RADIUS / 2.0, cy - PLANET_RADIUS / 2.0, PLANET_RADIUS);
+                let _ = write!(svg, r##"<rect x="{left}" y="{top}" width="{size}" height="{size}" fill="#1c1f2b" transform="rotate(45 {cx} {cy})"/>"##);
+                continue;
+            };
+            let (resources, opacity) = match get_view(planet) {
+                PlanetView::Visible => (planet.resources.as_ref(), 1.0),
+                PlanetView::Remembered(resources) => (resources, 0.45),
+                PlanetView::Unknown => {
+                    let _ = write!(svg, r##"<circle cx="{cx}" cy="{cy}" r="{PLANET_RADIUS}" fill="#11141f" stroke="#2b3147" stroke-dasharray="3 3"/>"##);
+                    continue;
+                }
+            };
+            let (ring_colour, ring_width) = match planet.movement_difficulty {
+                0 | 1 => ("#5a6b8c", 1.5),
+                2 => ("#9fb3d9", 2.5),
+                _ => ("#ffffff", 3.5),
+            };
+            let fill = resources.map(|(resource, _)| get_resource_colour(resource)).unwrap_or("#27304a");
+            let _ = write!(svg, r##"<circle cx="{cx}" cy="{cy}" r="{PLANET_RADIUS}" fill="{fill}" stroke="{ring_colour}" stroke-width="{ring_width}" opacity="{opacity}"/>"##);
+        }
+    }
+
+    let previous_round_state = round_number.checked_sub(1).and_then(|previous_round| game_state.round_states.get(&previous_round));
+    if let Some(previous_round_state) = previous_round_state.filter(|_| show_round_events) {
+        let mut battle_positions = HashSet::new();
+        for (player_index, other_player_name) in game_state.participating_players.iter().enumerate() {
+            let (Some(previous_player), Some(player)) = (previous_round_state.player_name_player_map.get(other_player_name), round_state.player_name_player_map.get(other_player_name)) else { continue };
+            let is_ally = player_name.is_none_or(|player_name| game_state.are_allies(player_name, other_player_name));
+            for previous_robot in previous_player.robots.values().filter(|robot| robot.is_alive()) {
+                let Some(robot) = player.robots.get(&previous_robot.robot_id) else { continue };
+                let (Some(&from), Some(&to)) = (round_state.map.indices.get(&previous_robot.planet_id), round_state.map.indices.get(&robot.planet_id)) else { continue };
+                if robot.health < previous_robot.health && is_visible(&robot.planet_id) {
+                    battle_positions.insert(to);
+                }
+                if from != to && from.0.abs_diff(to.0) <= 1 && from.1.abs_diff(to.1) <= 1 && (is_ally || (is_visible(&previous_robot.planet_id) && is_visible(&robot.planet_id))) {
+                    let ((x1, y1), (x2, y2)) = (center(from.0, from.1), center(to.0, to.1));
+                    let colour_index = player_index % PLAYER_COLOURS.len();
+                    let colour = PLAYER_COLOURS[colour_index];
+                    let _ = write!(svg, r##"<line x1="{x1}" y1="{y1}" x2="{}" y2="{}" stroke="{colour}" stroke-width="2.5" marker-end="url(#arrow-{colour_index})"/>"##,
+                                   x2 - (x2 - x1) * 0.35, y2 - (y2 - y1) * 0.35);
+                }
+            }
+        }
+        for &(x, y) in &battle_positions {
+            let (cx, cy) = center(x, y);
+            let _ = write!(svg, r##"<circle cx="{cx}" cy="{cy}" r="{}" fill="none" stroke="#ff3b30" stroke-width="2.5" stroke-dasharray="4 2"/>"##, PLANET_RADIUS + 6.0);
+        }
+        for (planet_id, &(x, y)) in &round_state.map.indices {
+            let Some(previous_planet) = previous_round_state.map.get_planet(planet_id) else { continue };
+            let amount = |planet: &Planet| planet.resources.as_ref().map(|(_, amount)| *amount).unwrap_or(0);
+            if amount(planets[x][y].as_ref().unwrap()) < amount(previous_planet) && is_visible(planet_id) {
+                let (cx, cy) = center(x, y);
+                let _ = write!(svg, r##"<circle cx="{cx}" cy="{cy}" r="{}" fill="none" stroke="#ffd700" stroke-width="1.5"/>"##, PLANET_RADIUS + 3.0);
+            }
+        }
+    }
+
+    // Alive robots per planet and player, as badges around the planet
+    let mut robots_per_planet: BTreeMap<(usize, usize), BTreeMap<usize, u32>> = BTreeMap::new();
+    for (player_index, other_player_name) in game_state.participating_players.iter().enumerate() {
+        let Some(player) = round_state.player_name_player_map.get(other_player_name) else { continue };
+        let is_ally = player_name.is_none_or(|player_name| game_state.are_allies(player_name, other_player_name));
+        for robot in player.robots.values().filter(|robot| robot.is_alive() && (is_ally || is_visible(&robot.planet_id))) {
+            let Some(&position) = round_state.map.indices.get(&robot.planet_id) else { continue };
+            *robots_per_planet.entry(position).or_default().entry(player_index).or_default() += 1;
+        }
+    }
+    for ((x, y), robots_per_player) in &robots_per_planet {
+        let (cx, cy) = center(*x, *y);
+        for (badge_index, (player_index, robot_count)) in robots_per_player.iter().enumerate() {
+            let angle = -std::f32::consts::FRAC_PI_4 + badge_index as f32 * std::f32::consts::FRAC_PI_2;
+            let (badge_x, badge_y) = (cx + PLANET_RADIUS * angle.cos(), cy + PLANET_RADIUS * angle.sin());
+            let colour = PLAYER_COLOURS[player_index % PLAYER_COLOURS.len()];
+            let _ = write!(svg, r##"<circle cx="{badge_x}" cy="{badge_y}" r="7" fill="{colour}" stroke="#0b1021" stroke-width="1.5"/>"##);
+            let _ = write!(svg, r##"<text x="{badge_x}" y="{}" font-size="9" font-weight="bold" fill="#ffffff" text-anchor="middle">{robot_count}</text>"##, badge_y + 3.0);
+        }
+    }
+
+    // Legend with the round and the colours of the players
+    let legend_y = height - LEGEND_HEIGHT / 2.0;
+    let _ = write!(svg, r##"<text x="{MARGIN}" y="{}" font-size="12" fill="#ffffff">Round {round_number}</text>"##, legend_y + 4.0);
+    for (player_index, other_player_name) in game_state.participating_players.iter().enumerate() {
+        let legend_x = MARGIN + 80.0 + player_index as f32 * 110.0;
+        let colour = PLAYER_COLOURS[player_index % PLAYER_COLOURS.len()];
+        let _ = write!(svg, r##"<circle cx="{legend_x}" cy="{legend_y}" r="6" fill="{colour}"/>"##);
+        let _ = write!(svg, r##"<text x="{}" y="{}" font-size="12" fill="#ffffff">{}</text>"##, legend_x + 10.0, legend_y + 4.0, escape_xml(other_player_name));
+    }
+    svg.push_str("</svg>");
+    Some(svg)
+}
+
+/// Rasterizes an SVG created by `render_svg`, scaled down to at most `MAX_IMAGE_SIZE` pixels on the longer side.
+/// Texts need fonts installed on the system, without them only the planets and robot badges are drawn.
+pub fn render_pixmap(svg: &str) -> Result<Pixmap, RenderError> {
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    let fonts = FONTS.get_or_init(|| {
+        let mut fonts = usvg::fontdb::Database::new();
+        fonts.load_system_fonts();
+        Arc::new(fonts)
+    });
+    let options = usvg::Options { fontdb: fonts.clone(), ..usvg::Options::default() };
+    let tree = usvg::Tree::from_str(svg, &options)?;
+    let scale = (MAX_IMAGE_SIZE / tree.size().width().max(tree.size().height())).min(1.0);
+    let (width, height) = ((tree.size().width() * scale).ceil() as u32, (tree.size().height() * scale).ceil() as u32);
+    let mut pixmap = Pixmap::new(width, height).ok_or(RenderError::InvalidSize(width, height))?;
+    resvg::render(&tree, Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+    Ok(pixmap)
+}
+
+pub fn render_png(svg: &str) -> Result<Vec<u8>, RenderError> {
+    render_pixmap(svg)?.encode_png().map_err(|error| RenderError::Encoding(error.to_string()))
+}
+
+fn get_resource_colour(resource: &Resource) -> &'static str {
+    match resource {
+        Resource::COAL => "#5c5c5c",
+        Resource::IRON => "#b7410e",
+        Resource::GEM => "#2e8b57",
+        Resource::GOLD => "#ffd700",
+        Resource::PLATINUM => "#e5e4e2",
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::game::game_rules::{GameRules, Visibility};
+    use crate::planet::map_generator::MapGenerator;
+    use crate::planet::map_symmetry::MapSymmetry;
+    use crate::planet::map_topology::MapTopology;
+    use crate::planet::resource_profile::ResourceProfile;
+    use crate::test_support::{add_players, create_robots};
+
+    use super::*;
+
+    #[test]
+    fn test_render_map_with_fog_of_war() {
+        let (planets, spawn_points) = MapGenerator::create_map_with_topology(15, &MapTopology::Grid, &MapSymmetry::Rotational { folds: 2 }, &ResourceProfile::default(), &mut StdRng::seed_from_u64(1)).unwrap();
+        let rules = GameRules { visibility: Visibility::Fog { sight_radius: 1 }, ..GameRules::default() };
+        let mut game_state = GameState::new(Uuid::new_v4(), 10, 2, planets, rules, 0);
+        add_players(&mut game_state, ["alice", "<bob>"].into_iter().zip(spawn_points).map(|(player_name, spawn_point)| (player_name, create_robots(3, spawn_point))).collect());
+
+        let full_map = render_svg(&game_state, 0, None).unwrap();
+        assert_eq!(full_map.matches(">3</text>").count(), 2);
+        assert!(full_map.contains("&lt;bob&gt;"));
+        let alices_map = render_svg(&game_state, 0, Some("alice")).unwrap();
+        assert_eq!(alices_map.matches(">3</text>").count(), 1);
+        assert!(alices_map.contains("stroke-dasharray"));
+        assert!(render_svg(&game_state, 1, None).is_none());
+        assert!(render_svg(&game_state, 0, Some("carol")).is_none());
+
+        let png = render_png(&alices_map).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let pixmap = render_pixmap(&full_map).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (768, 796));
+        if let Ok(path) = std::env::var("MAP_RENDER_OUTPUT") {
+            std::fs::write(path, render_png(&full_map).unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_large_maps_are_scaled_down() {
+        let (planets, _) = MapGenerator::create_map_with_topology(63, &MapTopology::Grid, &MapSymmetry::None, &ResourceProfile::default(), &mut StdRng::seed_from_u64(1)).unwrap();
+        let game_state = GameState::new(Uuid::new_v4(), 10, 2, planets, GameRules::default(), 0);
+        let pixmap = render_pixmap(&render_svg(&game_state, 0, None).unwrap()).unwrap();
+        assert_eq!(pixmap.width().max(pixmap.height()), MAX_IMAGE_SIZE as u32);
+    }
+}
@@ -1 +1,6 @@
 pub mod game_state;
+pub mod game_rules;
+pub mod game_results;
+pub mod map_renderer;
+pub mod render_error;
+pub mod replay;
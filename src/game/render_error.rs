@@ -0,0 +1,14 @@
+use thiserror::Error;
+
+/// Reasons why a map could not be rendered as an image.
+#[derive(Error, Debug)]
+pub enum RenderError {
+    #[error("Rendered SVG is not valid: {0}")]
+    InvalidSvg(#[from] resvg::usvg::Error),
+    #[error("Image of {0}x{1} pixels can't be created")]
+    InvalidSize(u32, u32),
+    #[error("Image could not be encoded: {0}")]
+    Encoding(String),
+    #[error("Image could not be written: {0}")]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,100 @@
+use std::path::Path;
+
+use crate::game::game_state::GameState;
+use crate::game::map_renderer::{render_pixmap, render_replay_frame_svg};
+use crate::game::render_error::RenderError;
+
+/// Draws every stored round of the game in order, see `render_replay_frame_svg`. Rounds the player didn't take part
+/// in are left out. Rounds are only drawn when the next frame is taken, so that a long game isn't held in memory.
+fn render_replay_frames<'a>(game_state: &'a GameState, player_name: Option<&'a str>) -> impl Iterator<Item = (u16, String)> + 'a {
+    let mut round_numbers: Vec<u16> = game_state.round_states.keys().copied().collect();
+    round_numbers.sort();
+    round_numbers.into_iter()
+        .filter_map(move |round_number| render_replay_frame_svg(game_state, round_number, player_name).map(|svg| (round_number, svg)))
+}
+
+/// Renders the whole game as an animated GIF which loops forever, showing every round for `frame_duration_ms`.
+/// Every frame is encoded before the next one is rasterized, frames are as large as `render_pixmap` allows.
+pub fn render_replay_gif(game_state: &GameState, player_name: Option<&str>, frame_duration_ms: u16) -> Result<Vec<u8>, RenderError> {
+    let mut pixmaps = render_replay_frames(game_state, player_name).map(|(_, svg)| render_pixmap(&svg));
+    // The size of the GIF is taken from the first frame, all frames show the same map
+    let first_pixmap = pixmaps.next().transpose()?;
+    let (width, height) = first_pixmap.as_ref().map(|pixmap| (pixmap.width() as u16, pixmap.height() as u16)).unwrap_or((1, 1));
+    let mut gif = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut gif, width, height, &[]).map_err(|error| RenderError::Encoding(error.to_string()))?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(|error| RenderError::Encoding(error.to_string()))?;
+        for pixmap in first_pixmap.into_iter().map(Ok).chain(pixmaps) {
+            let mut pixmap = pixmap?;
+            let mut frame = gif::Frame::from_rgba_speed(width, height, pixmap.data_mut(), 10);
+            frame.delay = frame_duration_ms / 10; // In hundredths of a second
+            encoder.write_frame(&frame).map_err(|error| RenderError::Encoding(error.to_string()))?;
+        }
+    }
+    Ok(gif)
+}
+
+/// Writes every round of the game as `round_0000.png`, `round_0001.png`, ... into the directory and returns how many
+/// frames were written.
+pub fn write_replay_frames(game_state: &GameState, player_name: Option<&str>, directory: &Path) -> Result<usize, RenderError> {
+    std::fs::create_dir_all(directory)?;
+    let mut frames = 0;
+    for (round_number, svg) in render_replay_frames(game_state, player_name) {
+        let png = render_pixmap(&svg)?.encode_png().map_err(|error| RenderError::Encoding(error.to_string()))?;
+        std::fs::write(directory.join(format!("round_{round_number:04}.png")), png)?;
+        frames += 1;
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::game::game_rules::GameRules;
+    use crate::game::map_renderer::render_replay_frame_svg;
+    use crate::planet::direction::Direction;
+    use crate::planet::planet::Planet;
+    use crate::planet::resource::Resource;
+    use crate::player::PlayerState;
+    use crate::robot::robot::Robot;
+
+    use super::*;
+
+    #[test]
+    fn test_replay_shows_moves_and_mining() {
+        let mut start = Planet::new(Uuid::new_v4(), 1);
+        let mut destination = Planet::new(Uuid::new_v4(), 1);
+        start.set_neighbour(Direction::EAST, destination.planet_id);
+        destination.set_neighbour(Direction::WEST, start.planet_id);
+        destination.resources = Some((Resource::COAL, 100));
+        let (start_id, destination_id) = (start.planet_id, destination.planet_id);
+        let mut game_state = GameState::new(Uuid::new_v4(), 10, 1, vec![vec![Some(start)], vec![Some(destination)]], GameRules::default(), 0);
+        let mut player = PlayerState::new("alice".to_string(), 500);
+        let robot = Robot::new(Uuid::new_v4(), start_id);
+        let robot_id = robot.robot_id;
+        player.robots.insert(robot_id, robot);
+        game_state.participating_players.push("alice".to_string());
+        game_state.round_states.get_mut(&0).unwrap().player_name_player_map.insert("alice".to_string(), player);
+        let mut next_round_state = game_state.round_states[&0].clone();
+        next_round_state.round_number = 1;
+        next_round_state.player_name_player_map.get_mut("alice").unwrap().robots.get_mut(&robot_id).unwrap().planet_id = destination_id;
+        next_round_state.map.get_planet_as_mut(&destination_id).unwrap().resources = Some((Resource::COAL, 90));
+        game_state.round_states.insert(1, next_round_state);
+
+        assert!(!render_replay_frame_svg(&game_state, 0, None).unwrap().contains("marker-end"));
+        let second_frame = render_replay_frame_svg(&game_state, 1, None).unwrap();
+        assert!(second_frame.contains("marker-end"));
+        assert!(second_frame.contains(r##"stroke="#ffd700""##));
+
+        let gif = render_replay_gif(&game_state, None, 500).unwrap();
+        assert_eq!(&gif[..6], b"GIF89a");
+        // The GIF is as large as a single rasterized frame, which is capped to MAX_IMAGE_SIZE
+        let first_frame = render_pixmap(&render_replay_frame_svg(&game_state, 0, None).unwrap()).unwrap();
+        assert_eq!((u16::from_le_bytes([gif[6], gif[7]]), u16::from_le_bytes([gif[8], gif[9]])), (first_frame.width() as u16, first_frame.height() as u16));
+        let directory = std::env::temp_dir().join(format!("replay-{}", game_state.game_id));
+        assert_eq!(write_replay_frames(&game_state, Some("alice"), &directory).unwrap(), 2);
+        assert!(directory.join("round_0001.png").exists());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
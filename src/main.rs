@@ -2,7 +2,7 @@ use actix_cors::Cors;
 use actix_web::{HttpResponse, HttpServer, Responder};
 use actix_web::web::Data;
 use mobc_redis::RedisConnectionManager;
-use redis::Commands;
+use mobc_redis::redis::Commands;
 use tracing::{debug, error, info, Level};
 use tracing_subscriber::{EnvFilter, fmt};
 use tracing_subscriber::prelude::*;
@@ -15,10 +15,28 @@ mod robot;
 mod trading;
 mod api;
 mod player;
+mod bot;
+mod tournament;
+#[cfg(test)]
+mod test_support;
 #[actix_web::get("/")]
 async fn hello_world() -> impl Responder {
     HttpResponse:: Ok().body("Hello, world!")
 }
+fn write_replay_frames_of_stored_game(client: &mobc_redis::redis::Client, arguments: &[String]) -> Result<(), std::io::Error> {
+    let (Some(game_id), Some(output_directory)) = (arguments.first(), arguments.get(1)) else {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "Usage: replay-frames <game_id> <output_directory> [player_name]"));
+    };
+    let mut con = client.get_connection().map_err(std::io::Error::other)?;
+    let game: Option<String> = con.get(format!("games/{}", game_id)).map_err(std::io::Error::other)?;
+    let game = game.ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, format!("Game {game_id} was not found")))?;
+    let game_state: game::game_state::GameState = serde_json::from_str(&game).map_err(std::io::Error::other)?;
+    let frames = game::replay::write_replay_frames(&game_state, arguments.get(2).map(String::as_str), std::path::Path::new(output_directory))
+        .map_err(std::io::Error::other)?;
+    info!("Wrote {} frames of game {} to {}", frames, game_id, output_directory);
+    Ok(())
+}
+
 #[actix_web::main]
 async fn main() -> Result<(),std::io::Error>{
     let redis_host = std::env::var("REDIS_HOST").unwrap_or("127.0.0.1".into());
@@ -33,6 +51,11 @@ async fn main() -> Result<(),std::io::Error>{
         .init();
     debug!("Starting server");
     let client = mobc_redis::redis::Client::open(format!("redis://{}:{}",redis_host,redis_port)).expect("Invalid redis url");
+    // `replay-frames <game_id> <output_directory> [player_name]` writes the rounds of a stored game as PNG files instead of starting the server
+    let arguments: Vec<String> = std::env::args().collect();
+    if arguments.get(1).map(String::as_str) == Some("replay-frames") {
+        return write_replay_frames_of_stored_game(&client, &arguments[2..]);
+    }
     let pool_manager = RedisConnectionManager::new(client);
     let pool = mobc::Pool::builder()
         .max_open(10)
@@ -47,6 +70,7 @@ async fn main() -> Result<(),std::io::Error>{
             .app_data(Data::clone(&pool_as_sharable_data))
             .service(hello_world)
             .configure(api::games::game_routes)
+            .configure(api::tournaments::tournament_routes)
     })
         .bind("0.0.0.0:8080")
         .expect("Failed to bind to port")
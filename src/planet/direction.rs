@@ -1,11 +1,17 @@
 use serde::{Deserialize, Serialize};
 
+/// Direction of a neighbouring planet. Square grids only use the four main directions, hex grids use NORTH, SOUTH
+/// and the four diagonal directions.
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Hash, Clone)]
 pub enum Direction {
     NORTH,
     EAST,
     SOUTH,
     WEST,
+    NORTHEAST,
+    SOUTHEAST,
+    SOUTHWEST,
+    NORTHWEST,
 }
 
 impl Direction {
@@ -15,6 +21,10 @@ impl Direction {
             Direction::EAST => Direction::WEST,
             Direction::SOUTH => Direction::NORTH,
             Direction::WEST => Direction::EAST,
+            Direction::NORTHEAST => Direction::SOUTHWEST,
+            Direction::SOUTHEAST => Direction::NORTHWEST,
+            Direction::SOUTHWEST => Direction::NORTHEAST,
+            Direction::NORTHWEST => Direction::SOUTHEAST,
         }
     }
 
@@ -24,6 +34,10 @@ impl Direction {
             Direction::EAST,
             Direction::SOUTH,
             Direction::WEST,
+            Direction::NORTHEAST,
+            Direction::SOUTHEAST,
+            Direction::SOUTHWEST,
+            Direction::NORTHWEST,
         ]
     }
 
@@ -33,7 +47,11 @@ impl Direction {
             "EAST" => Some(Direction::EAST),
             "SOUTH" => Some(Direction::SOUTH),
             "WEST" => Some(Direction::WEST),
+            "NORTHEAST" => Some(Direction::NORTHEAST),
+            "SOUTHEAST" => Some(Direction::SOUTHEAST),
+            "SOUTHWEST" => Some(Direction::SOUTHWEST),
+            "NORTHWEST" => Some(Direction::NORTHWEST),
             _ => None,
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,18 @@
+use thiserror::Error;
+
+/// Reasons why a map could not be created.
+#[derive(Error, Debug)]
+pub enum MapError {
+    #[error("Map file {0} could not be read: {1}")]
+    FileNotReadable(String, #[source] std::io::Error),
+    #[error("Map file is not valid: {0}")]
+    InvalidFile(#[from] serde_json::Error),
+    #[error("Map file version {0} is not supported")]
+    UnsupportedVersion(u32),
+    #[error("Invalid map: {0}")]
+    InvalidMap(String),
+    #[error("Only {placed} of {wanted} obstacles could be placed without splitting the map")]
+    ObstaclesNotPlaced { placed: usize, wanted: usize },
+    #[error("Not all planets of the map are connected")]
+    NotConnected,
+}
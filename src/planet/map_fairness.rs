@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::game::game_rules::MovementCostModel;
+use crate::game::game_state::GameMap;
+use crate::planet::pathfinding::get_energy_costs_from;
+use crate::planet::planet::Planet;
+use crate::robot::robot_levels::RobotLevels;
+
+/// Compares the resources around the starts of a map, so unfair maps can be spotted before they are played.
+#[derive(Serialize, Debug, Clone)]
+pub struct FairnessReport {
+    pub starts: Vec<StartFairness>,
+    /// Difference between the richest and the poorest start, relative to the richest one (0 is perfectly fair).
+    pub closest_resource_value_spread: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StartFairness {
+    pub planet_id: Uuid,
+    pub player_name: Option<String>,
+    /// Planets which can be reached with less energy from this start than from any other start.
+    pub closest_planets: f64,
+    /// Selling value of the resources on these planets, shared equally with other starts which are as close.
+    pub closest_resource_value: f64,
+    /// Selling value of the resources which a new robot can reach without regenerating.
+    pub resource_value_within_reach: u64,
+}
+
+impl FairnessReport {
+    pub fn new(map: &GameMap, movement_cost_model: &MovementCostModel, starts: Vec<(Uuid, Option<String>)>) -> FairnessReport {
+        let energy_of_new_robot = RobotLevels::default().get_energy_for_level();
+        let energy_costs: Vec<HashMap<Uuid, u32>> = starts.iter().map(|(planet_id, _)| get_energy_costs_from(map, movement_cost_model, None, *planet_id)).collect();
+        let mut start_fairness: Vec<StartFairness> = starts.into_iter().zip(&energy_costs)
+            .map(|((planet_id, player_name), energy_costs)| StartFairness {
+                planet_id,
+                player_name,
+                closest_planets: 0.0,
+                closest_resource_value: 0.0,
+                resource_value_within_reach: energy_costs.iter()
+                    .filter(|(_, energy_cost)| **energy_cost <= energy_of_new_robot)
+                    .filter_map(|(planet_id, _)| map.get_planet(planet_id))
+                    .map(get_resource_value)
+                    .sum(),
+            })
+            .collect();
+
+        for planet in map.planets.iter().flatten().flatten() {
+            let costs: Vec<Option<u32>> = energy_costs.iter().map(|energy_costs| energy_costs.get(&planet.planet_id).copied()).collect();
+            let Some(lowest_cost) = costs.iter().flatten().min().copied() else { continue };
+            let closest_starts: Vec<usize> = (0..costs.len()).filter(|index| costs[*index] == Some(lowest_cost)).collect();
+            for index in &closest_starts {
+                start_fairness[*index].closest_planets += 1.0 / closest_starts.len() as f64;
+                start_fairness[*index].closest_resource_value += get_resource_value(planet) as f64 / closest_starts.len() as f64;
+            }
+        }
+
+        let values = start_fairness.iter().map(|start| start.closest_resource_value);
+        let (lowest_value, highest_value) = values.fold((f64::MAX, 0.0f64), |(lowest, highest), value| (lowest.min(value), highest.max(value)));
+        FairnessReport {
+            starts: start_fairness,
+            closest_resource_value_spread: if highest_value > 0.0 { (highest_value - lowest_value) / highest_value } else { 0.0 },
+        }
+    }
+}
+
+fn get_resource_value(planet: &Planet) -> u64 {
+    planet.resources.as_ref().map(|(resource, amount)| resource.get_selling_value() as u64 * *amount as u64).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::game::game_rules::GameRules;
+    use crate::game::game_state::GameState;
+    use crate::planet::map_generator::MapGenerator;
+    use crate::planet::map_symmetry::MapSymmetry;
+    use crate::planet::map_topology::MapTopology;
+    use crate::planet::resource_profile::ResourceProfile;
+
+    use super::*;
+
+    #[test]
+    fn test_symmetric_maps_are_fair() {
+        let (planets, spawn_points) = MapGenerator::create_map_with_topology(15, &MapTopology::Grid, &MapSymmetry::Rotational { folds: 4 }, &ResourceProfile::default(), &mut StdRng::seed_from_u64(3)).unwrap();
+        let game_state = GameState::new(Uuid::new_v4(), 10, 4, planets, GameRules::default(), 0);
+        let map = &game_state.round_states[&0].map;
+        for movement_cost_model in [MovementCostModel::Source, MovementCostModel::Destination] {
+            let report = FairnessReport::new(map, &movement_cost_model, spawn_points.iter().map(|spawn_point| (*spawn_point, None)).collect());
+            assert_eq!(report.starts.len(), 4);
+            assert!(report.closest_resource_value_spread < 1e-9);
+            assert!(report.starts.iter().all(|start| start.resource_value_within_reach == report.starts[0].resource_value_within_reach));
+        }
+
+        let unfair_report = FairnessReport::new(map, &MovementCostModel::Source, vec![(spawn_points[0], None), (spawn_points[0], None), (spawn_points[1], None)]);
+        assert!(unfair_report.closest_resource_value_spread > 0.4);
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::game::game_state::GameMap;
+use crate::planet::direction::Direction;
+use crate::planet::map_error::MapError;
+use crate::planet::map_generator::{CreatedMap, MapGenerator};
+use crate::planet::planet::Planet;
+use crate::planet::resource::Resource;
+
+/// Maps which are compiled into the binary, by name.
+const PRESETS: [(&str, &str); 3] = [
+    ("duel", include_str!("presets/duel.json")),
+    ("crossroads", include_str!("presets/crossroads.json")),
+    ("ring", include_str!("presets/ring.json")),
+];
+
+/// A map stored as JSON. Planets reference each other by the `id` they have within the file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MapFile {
+    #[serde(default = "MapFile::current_version")]
+    pub version: u32,
+    pub planets: Vec<MapFilePlanet>,
+    /// Ids of the planets on which bought robots are spawned. Robots spawn on any planet if there are none.
+    #[serde(default)]
+    pub spawn_points: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MapFilePlanet {
+    pub id: String,
+    pub x: usize,
+    pub y: usize,
+    pub movement_difficulty: u8,
+    #[serde(default)]
+    pub resources: Option<(Resource, u32)>,
+    /// Only needs to be listed on one of both planets, the opposite direction is added automatically.
+    #[serde(default)]
+    pub neighbours: HashMap<Direction, String>,
+}
+
+impl MapFile {
+    pub const CURRENT_VERSION: u32 = 1;
+    /// Largest width and height of a map, the same as for generated maps, whose size is a `u8`.
+    pub const MAX_SIZE: usize = u8::MAX as usize + 1;
+    pub const MAX_PLANETS: usize = MapFile::MAX_SIZE * MapFile::MAX_SIZE;
+
+    fn current_version() -> u32 {
+        MapFile::CURRENT_VERSION
+    }
+
+    pub fn get_preset_names() -> Vec<&'static str> {
+        PRESETS.iter().map(|(name, _)| *name).collect()
+    }
+
+    pub fn get_preset(name: &str) -> Result<MapFile, MapError> {
+        let (_, json) = PRESETS.iter().find(|(preset_name, _)| *preset_name == name)
+            .ok_or_else(|| MapError::InvalidMap(format!("There is no map preset named {name}")))?;
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Describes the map, so it can be loaded again in another game. Planet ids are kept as the ids within the file.
+    pub fn from_game_map(map: &GameMap) -> MapFile {
+        let planets = map.planets.iter().enumerate()
+            .flat_map(|(x, row)| row.iter().enumerate().filter_map(move |(y, planet)| planet.as_ref().map(|planet| (x, y, planet))))
+            .map(|(x, y, planet)| MapFilePlanet {
+                id: planet.planet_id.to_string(),
+                x,
+                y,
+                movement_difficulty: planet.movement_difficulty,
+                resources: planet.resources.clone(),
+                neighbours: planet.neighbours.iter().map(|(direction, neighbour_id)| (direction.clone(), neighbour_id.to_string())).collect(),
+            })
+            .collect();
+        MapFile {
+            version: MapFile::CURRENT_VERSION,
+            planets,
+            spawn_points: map.spawn_points.iter().map(|planet_id| planet_id.to_string()).collect(),
+        }
+    }
+
+    /// Loads `{name}.json` from the directory in the `MAPS_DIR` environment variable, or from `maps`.
+    pub fn load(name: &str) -> Result<MapFile, MapError> {
+        if name.is_empty() || !name.chars().all(|character| character.is_ascii_alphanumeric() || character == '-' || character == '_') {
+            return Err(MapError::InvalidMap(format!("{name} is not a valid map name")));
+        }
+        let mut path = PathBuf::from(std::env::var("MAPS_DIR").unwrap_or_else(|_| "maps".to_string()));
+        path.push(format!("{name}.json"));
+        let json = std::fs::read_to_string(&path).map_err(|error| MapError::FileNotReadable(path.display().to_string(), error))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Creates the planets of the map, placed in a square grid by their coordinates, and the ids of the spawn points.
+    pub fn to_planets(&self, rng: &mut impl Rng) -> Result<CreatedMap, MapError> {
+        if self.version == 0 || self.version > MapFile::CURRENT_VERSION {
+            return Err(MapError::UnsupportedVersion(self.version));
+        }
+        if self.planets.len() > MapFile::MAX_PLANETS {
+            return Err(MapError::InvalidMap(format!("The map has more than {} planets", MapFile::MAX_PLANETS)));
+        }
+        let size = self.planets.iter().map(|planet| std::cmp::max(planet.x, planet.y).saturating_add(1)).max()
+            .ok_or_else(|| MapError::InvalidMap("The map has no planets".to_string()))?;
+        if size > MapFile::MAX_SIZE {
+            return Err(MapError::InvalidMap(format!("The map is larger than {0}x{0}", MapFile::MAX_SIZE)));
+        }
+        let mut planets: Vec<Vec<Option<Planet>>> = vec![vec![None; size]; size];
+        let mut positions: HashMap<&str, (usize, usize)> = HashMap::new();
+        for file_planet in &self.planets {
+            if planets[file_planet.x][file_planet.y].is_some() {
+                return Err(MapError::InvalidMap(format!("There is more than one planet at ({}, {})", file_planet.x, file_planet.y)));
+            }
+            if positions.insert(&file_planet.id, (file_planet.x, file_planet.y)).is_some() {
+                return Err(MapError::InvalidMap(format!("There is more than one planet with id {}", file_planet.id)));
+            }
+            let mut planet = Planet::new(MapGenerator::create_planet_id(rng), file_planet.movement_difficulty);
+            planet.resources = file_planet.resources.clone();
+            planets[file_planet.x][file_planet.y] = Some(planet);
+        }
+
+        for file_planet in &self.planets {
+            for (direction, neighbour_id) in &file_planet.neighbours {
+                let &(neighbour_x, neighbour_y) = positions.get(neighbour_id.as_str())
+                    .ok_or_else(|| MapError::InvalidMap(format!("Planet {} has the unknown neighbour {}", file_planet.id, neighbour_id)))?;
+                for ((x, y), (other_x, other_y), direction) in [
+                    ((file_planet.x, file_planet.y), (neighbour_x, neighbour_y), direction.clone()),
+                    ((neighbour_x, neighbour_y), (file_planet.x, file_planet.y), direction.get_opposite()),
+                ] {
+                    let other_planet_id = planets[other_x][other_y].as_ref().unwrap().planet_id;
+                    let planet = planets[x][y].as_mut().unwrap();
+                    match planet.neighbours.get(&direction) {
+                        Some(existing_neighbour_id) if *existing_neighbour_id != other_planet_id => {
+                            return Err(MapError::InvalidMap(format!("The planet at ({x}, {y}) has more than one neighbour to the {direction:?}")));
+                        }
+                        _ => planet.set_neighbour(direction, other_planet_id),
+                    }
+                }
+            }
+        }
+
+        let planet_positions: HashMap<_, _> = positions.values().map(|&(x, y)| (planets[x][y].as_ref().unwrap().planet_id, (x, y))).collect();
+        let is_connected = MapGenerator::all_planets_connected(&planets, |x, y| {
+            planets[x][y].as_ref().map(|planet| planet.neighbours.values().map(|neighbour_id| planet_positions[neighbour_id]).collect()).unwrap_or_default()
+        });
+        if !is_connected {
+            return Err(MapError::NotConnected);
+        }
+
+        let spawn_points = self.spawn_points.iter()
+            .map(|spawn_point| positions.get(spawn_point.as_str())
+                .map(|&(x, y)| planets[x][y].as_ref().unwrap().planet_id)
+                .ok_or_else(|| MapError::InvalidMap(format!("The spawn point {spawn_point} is not a planet of the map"))))
+            .collect::<Result<Vec<Uuid>, MapError>>()?;
+        Ok((planets, spawn_points))
+    }
+}
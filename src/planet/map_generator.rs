@@ -1,14 +1,24 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 
-use rand::distributions::{Distribution, WeightedIndex};
 use rand::Rng;
 use rayon::prelude::*;
 use tracing::log::info;
 use uuid::Uuid;
 
 use crate::planet::direction::Direction;
+use crate::planet::map_error::MapError;
+use crate::planet::map_file::MapFile;
+use crate::planet::map_symmetry::MapSymmetry;
+use crate::planet::map_topology::MapTopology;
 use crate::planet::planet::Planet;
 use crate::planet::resource::Resource;
+use crate::planet::resource_profile::ResourceProfile;
+
+/// How often a planet may turn out not to be removable, before map generation gives up on placing obstacles.
+const MAX_FAILED_REMOVALS: usize = 1000;
+
+/// The planets of a map, placed in a square grid, and the ids of the planets on which robots spawn.
+pub type CreatedMap = (Vec<Vec<Option<Planet>>>, Vec<Uuid>);
 
 pub struct MapGenerator {}
 
@@ -17,62 +27,70 @@ impl MapGenerator {
 
     Every planet has an movement difficulty, which defines how much energy you need, to move on this planet. The planets in the inner map (planets which are ((mapsize-1)/3) fields away from the edge) have a difficulty of 3. The planets in the mid map (planets which are ((mapsize-1)/3/2) fields away from the edge) have a difficulty of 2. All the other planets are in the outer map and have a difficulty of 1.
 
-The location of the planets also defines which ressources they can contain. The outer map only contains cole. The mid map contains iron and gems and the inner map can contain gold and platin. The distribution of the resoure should be 80% coal, 10% iron, 5% gems, 3% gold and 2% platin.
+The location of the planets also defines which ressources they can contain. The outer map only contains cole. The mid map contains iron and gems and the inner map can contain gold and platin. How many planets of each ring contain resources and how large the deposits are is set by the ResourceProfile of the game.
 
 Some planets of the map are not accessable. They are not part of the neighbours of the accessing planets or resources. They represent obstacles for the players, where they have to move around. Their location is also chosen randomly.
 The relationship between planets is express as neighbour relation (Direction(North,West,South,East))
      */
 
-    pub fn create_map(size: usize) -> Vec<Vec<Option<Planet>>> {
-        let mut planets = vec![vec![Some(Planet::new(Uuid::new_v4(), 0)); size]; size];
-        let mut rng = rand::thread_rng();
-        for x in 0..size {
-            for y in 0..size {
-                let mut planet = Planet::new(Uuid::new_v4(), MapGenerator::get_movement_difficulty(size, x, y));
-                if rng.gen_range(0..100) < 80 {
-                    planet.resources = Some(MapGenerator::get_resources(size, x, y));
-                }
-                planets[x][y] = Some(planet);
+    #[cfg(test)]
+    pub fn create_map(size: usize) -> Result<Vec<Vec<Option<Planet>>>, MapError> {
+        use rand::SeedableRng;
+        MapGenerator::create_map_with_topology(size, &MapTopology::Grid, &MapSymmetry::None, &ResourceProfile::default(), &mut rand::rngs::StdRng::from_entropy())
+            .map(|(planets, _)| planets)
+    }
+
+    /// Creates a map of the given topology and the ids of its spawn points. Spawn points are set by map files and
+    /// by symmetric maps, which get one home planet per sector.
+    /// All randomness comes from `rng`, so a seeded rng always creates the same map.
+    pub fn create_map_with_topology(size: usize, topology: &MapTopology, symmetry: &MapSymmetry, resource_profile: &ResourceProfile,
+                                    rng: &mut impl Rng) -> Result<CreatedMap, MapError> {
+        resource_profile.validate()?;
+        if *symmetry != MapSymmetry::None {
+            if !matches!(topology, MapTopology::Grid | MapTopology::Torus) {
+                return Err(MapError::InvalidMap(format!("{topology:?} maps can't be generated symmetrically")));
             }
-        }
-        //Remove "size" amount of random planets from the map.
-        let mut removed_planets = 0;
-        while removed_planets < size {
-            let x = rng.gen_range(0..size);
-            let y = rng.gen_range(0..size);
-            if planets[x][y].is_some() && MapGenerator::can_remove_planet(&mut planets, x, y, size) {
-                planets[x][y] = None;
-                removed_planets += 1;
+            if !matches!(symmetry, MapSymmetry::Rotational { folds: 2 | 4 }) {
+                return Err(MapError::InvalidMap("Symmetric maps need 2 or 4 folds".to_string()));
             }
         }
+        if size == 0 && !matches!(topology, MapTopology::File { .. }) {
+            return Err(MapError::InvalidMap("Generated maps need a size of at least 1".to_string()));
+        }
+        let mut home_positions = Vec::new();
+        let mut planets = match topology {
+            MapTopology::File { name } => return MapFile::load(name)?.to_planets(rng),
+            MapTopology::Islands { islands } => MapGenerator::create_island_planets(size, *islands, resource_profile, rng),
+            MapTopology::Grid | MapTopology::Hex | MapTopology::Torus => {
+                let mut planets: Vec<Vec<Option<Planet>>> = (0..size)
+                    .map(|x| (0..size).map(|y| Some(MapGenerator::create_planet(size, x, y, resource_profile, rng))).collect())
+                    .collect();
+                if *symmetry != MapSymmetry::None {
+                    MapGenerator::mirror_planets(&mut planets, symmetry);
+                    // Homes are placed near a corner of the first sector, so they are as far apart as possible.
+                    let corner = std::cmp::max(1, size / 4);
+                    home_positions = symmetry.get_orbit(rng.gen_range(0..corner), rng.gen_range(0..corner), size);
+                }
+                MapGenerator::remove_random_planets(&mut planets, size, topology, symmetry, &home_positions, rng)?;
+                planets
+            }
+        };
 
         // Stelle sicher, dass alle verbleibenden Planeten verbunden sind
-        if !MapGenerator::all_planets_connected(&planets, size) {
-            panic!("Nicht alle Planeten sind verbunden. Bitte die Map-Generierung erneut durchführen.");
+        if !MapGenerator::all_planets_connected(&planets, |x, y| MapGenerator::get_neighbour_positions(topology, x, y, size)) {
+            return Err(MapError::NotConnected);
         }
 
         //Nachbarschaften setzen.
         for x in 0..size {
             for y in 0..size {
-                let mut neighbours: HashMap<Direction,Uuid> = HashMap::new();
-                if x > 0 {
-                    if let Some(planet) = &planets[x-1][y] {
-                        neighbours.insert(Direction::WEST, planet.get_planet_id());
+                let mut neighbours: HashMap<Direction, Uuid> = HashMap::new();
+                for (direction, neighbour_x, neighbour_y) in topology.get_neighbour_positions(x, y, size) {
+                    if (neighbour_x, neighbour_y) == (x, y) {
+                        continue;
                     }
-                }
-                if x < size-1 {
-                    if let Some(planet) = &planets[x+1][y] {
-                        neighbours.insert(Direction::EAST, planet.get_planet_id());
-                    }
-                }
-                if y > 0 {
-                    if let Some(planet) = &planets[x][y-1] {
-                        neighbours.insert(Direction::NORTH, planet.get_planet_id());
-                    }
-                }
-                if y < size-1 {
-                    if let Some(planet) = &planets[x][y+1] {
-                        neighbours.insert(Direction::SOUTH, planet.get_planet_id());
+                    if let Some(planet) = &planets[neighbour_x][neighbour_y] {
+                        neighbours.insert(direction, planet.get_planet_id());
                     }
                 }
                 if let Some(planet) = &mut planets[x][y] {
@@ -87,60 +105,200 @@ The relationship between planets is express as neighbour relation (Direction(Nor
             .collect();
         let map_print_as_string = MapGenerator::display_map_with_connections(planets_ref_structure);
         info!("Map:\n{}", map_print_as_string);
-        return planets;
+        let spawn_points = home_positions.iter().map(|&(x, y)| planets[x][y].as_ref().unwrap().planet_id).collect();
+        Ok((planets, spawn_points))
     }
 
-    fn can_remove_planet(planets: &mut Vec<Vec<Option<Planet>>>, x: usize, y: usize, size: usize) -> bool {
-        // Entferne den Planeten temporär
-        let temp = planets[x][y].take();
+    /// Planet ids are drawn from the map's rng, so seeded maps get the same ids.
+    pub fn create_planet_id(rng: &mut impl Rng) -> Uuid {
+        uuid::Builder::from_random_bytes(rng.gen()).into_uuid()
+    }
 
-        let is_connected = MapGenerator::all_planets_connected(planets, size);
+    fn create_planet(size: usize, x: usize, y: usize, resource_profile: &ResourceProfile, rng: &mut impl Rng) -> Planet {
+        let mut planet = Planet::new(MapGenerator::create_planet_id(rng), MapGenerator::get_movement_difficulty(size, x, y));
+        planet.resources = resource_profile.get_resources(planet.movement_difficulty, rng);
+        planet
+    }
 
-        // Stelle den Planeten wieder her
-        planets[x][y] = temp;
+    /// Places `islands` diamonds of planets at random centers and joins consecutive islands with L-shaped corridors.
+    fn create_island_planets(size: usize, islands: u8, resource_profile: &ResourceProfile, rng: &mut impl Rng) -> Vec<Vec<Option<Planet>>> {
+        let islands = islands.max(1) as usize;
+        let radius = std::cmp::max(1, size / (islands + 3));
+        let margin = radius.min(size.saturating_sub(1) / 2);
+        let centers: Vec<(usize, usize)> = (0..islands)
+            .map(|_| (rng.gen_range(margin..size - margin), rng.gen_range(margin..size - margin)))
+            .collect();
 
-        is_connected
+        let mut land = HashSet::new();
+        for x in 0..size {
+            for y in 0..size {
+                if centers.iter().any(|&(center_x, center_y)| center_x.abs_diff(x) + center_y.abs_diff(y) <= radius) {
+                    land.insert((x, y));
+                }
+            }
+        }
+        for corridor in centers.windows(2) {
+            let ((from_x, from_y), (to_x, to_y)) = (corridor[0], corridor[1]);
+            for x in from_x.min(to_x)..=from_x.max(to_x) {
+                land.insert((x, from_y));
+            }
+            for y in from_y.min(to_y)..=from_y.max(to_y) {
+                land.insert((to_x, y));
+            }
+        }
+
+        (0..size)
+            .map(|x| (0..size).map(|y| land.contains(&(x, y)).then(|| MapGenerator::create_planet(size, x, y, resource_profile, rng))).collect())
+            .collect()
     }
 
-    fn all_planets_connected(planets: &Vec<Vec<Option<Planet>>>, size: usize) -> bool {
-        let mut visited = HashSet::new();
-        let mut queue = VecDeque::new();
+    fn get_neighbour_positions(topology: &MapTopology, x: usize, y: usize, size: usize) -> Vec<(usize, usize)> {
+        topology.get_neighbour_positions(x, y, size).into_iter().map(|(_, x, y)| (x, y)).collect()
+    }
 
-        // Finde den ersten vorhandenen Planeten und starte die Suche von dort
-        'outer: for x in 0..size {
-            for y in 0..size {
-                if planets[x][y].is_some() {
-                    queue.push_back((x, y));
-                    visited.insert((x, y));
-                    break 'outer;
+    /// Removes `count` random planets, together with their images in the other sectors, without splitting the map.
+    /// Most planets can be removed safely, which is seen by looking only at the planets around them. Only if that
+    /// isn't enough, the whole map is searched.
+    fn remove_random_planets(planets: &mut [Vec<Option<Planet>>], count: usize, topology: &MapTopology, symmetry: &MapSymmetry,
+                             protected_positions: &[(usize, usize)], rng: &mut impl Rng) -> Result<(), MapError> {
+        let size = planets.len();
+        let adjacent_positions = MapGenerator::get_adjacent_positions(topology, size);
+        let mut remaining_planets = planets.iter().flatten().flatten().count();
+        let mut removed_planets = 0;
+        let mut failed_attempts = 0;
+        while removed_planets < count {
+            if failed_attempts > MAX_FAILED_REMOVALS {
+                return Err(MapError::ObstaclesNotPlaced { placed: removed_planets, wanted: count });
+            }
+            let orbit = symmetry.get_orbit(rng.gen_range(0..size), rng.gen_range(0..size), size);
+            if orbit.len() >= remaining_planets {
+                // The map would be left without planets.
+                return Err(MapError::ObstaclesNotPlaced { placed: removed_planets, wanted: count });
+            }
+            if !orbit.iter().all(|&(x, y)| planets[x][y].is_some() && !protected_positions.contains(&(x, y))) {
+                failed_attempts += 1;
+                continue;
+            }
+
+            let mut removed_orbit = Vec::new();
+            for &(x, y) in &orbit {
+                if !MapGenerator::is_locally_removable(planets, &adjacent_positions, x * size + y) {
+                    break;
+                }
+                removed_orbit.push(((x, y), planets[x][y].take()));
+            }
+            if removed_orbit.len() < orbit.len() {
+                for ((x, y), planet) in removed_orbit {
+                    planets[x][y] = planet;
+                }
+                if !MapGenerator::can_remove_planets(planets, &orbit, topology) {
+                    failed_attempts += 1;
+                    continue;
+                }
+                for &(x, y) in &orbit {
+                    planets[x][y] = None;
                 }
             }
+            remaining_planets -= orbit.len();
+            removed_planets += orbit.len();
         }
+        Ok(())
+    }
 
-        // Führe eine Breitensuche (BFS) durch, um alle erreichbaren Planeten zu besuchen
-        while let Some((x, y)) = queue.pop_front() {
-            for (dx, dy) in [(-1, 0), (0, -1), (1, 0), (0, 1)] {
-                let new_x = (x as isize + dx) as usize;
-                let new_y = (y as isize + dy) as usize;
-                if new_x < size && new_y < size && planets[new_x][new_y].is_some() && !visited.contains(&(new_x, new_y)) {
-                    queue.push_back((new_x, new_y));
-                    visited.insert((new_x, new_y));
+    fn can_remove_planets(planets: &mut [Vec<Option<Planet>>], positions: &[(usize, usize)], topology: &MapTopology) -> bool {
+        let size = planets.len();
+        // Entferne die Planeten temporär
+        let temp: Vec<Option<Planet>> = positions.iter().map(|&(x, y)| planets[x][y].take()).collect();
+
+        let is_connected = MapGenerator::all_planets_connected(planets, |x, y| MapGenerator::get_neighbour_positions(topology, x, y, size));
+
+        // Stelle die Planeten wieder her
+        for (&(x, y), planet) in positions.iter().zip(temp) {
+            planets[x][y] = planet;
+        }
+
+        is_connected
+    }
+
+    /// Returns the positions next to every position of the map, as indices `x * size + y`.
+    fn get_adjacent_positions(topology: &MapTopology, size: usize) -> Vec<Vec<usize>> {
+        (0..size * size)
+            .map(|index| MapGenerator::get_neighbour_positions(topology, index / size, index % size, size).into_iter().map(|(x, y)| x * size + y).collect())
+            .collect()
+    }
+
+    /// Checks whether the neighbours of the planet at `index` stay connected without it, using only the planets at most
+    /// two steps away. If they do, every route through the planet can go around it, so it can be removed without
+    /// splitting the map. If they don't, the planet may still be removable, which only a search of the whole map shows.
+    fn is_locally_removable(planets: &[Vec<Option<Planet>>], adjacent_positions: &[Vec<usize>], index: usize) -> bool {
+        let size = planets.len();
+        let is_planet = |position: usize| position != index && planets[position / size][position % size].is_some();
+        let neighbours: Vec<usize> = adjacent_positions[index].iter().copied().filter(|&position| is_planet(position)).collect();
+        let Some(&first_neighbour) = neighbours.first() else { return false };
+        let surroundings: HashSet<usize> = neighbours.iter()
+            .flat_map(|&neighbour| adjacent_positions[neighbour].iter().copied())
+            .filter(|&position| is_planet(position))
+            .chain(neighbours.iter().copied())
+            .collect();
+
+        let mut visited = HashSet::from([first_neighbour]);
+        let mut queue = VecDeque::from([first_neighbour]);
+        while let Some(position) = queue.pop_front() {
+            for &next_position in &adjacent_positions[position] {
+                if surroundings.contains(&next_position) && visited.insert(next_position) {
+                    queue.push_back(next_position);
                 }
             }
         }
+        neighbours.iter().all(|neighbour| visited.contains(neighbour))
+    }
 
-        // Überprüfe, ob alle Planeten besucht wurden
+    /// Gives every planet the movement difficulty and resources of its image in the first sector.
+    fn mirror_planets(planets: &mut [Vec<Option<Planet>>], symmetry: &MapSymmetry) {
+        let size = planets.len();
         for x in 0..size {
             for y in 0..size {
-                if planets[x][y].is_some() && !visited.contains(&(x, y)) {
-                    return false;
+                let (original_x, original_y) = symmetry.get_orbit(x, y, size).into_iter().min().unwrap();
+                let original = planets[original_x][original_y].as_ref().map(|planet| (planet.movement_difficulty, planet.resources.clone()));
+                if let (Some(planet), Some((movement_difficulty, resources))) = (planets[x][y].as_mut(), original) {
+                    planet.movement_difficulty = movement_difficulty;
+                    planet.resources = resources;
+                }
+            }
+        }
+    }
+
+    /// Checks whether every planet can be reached from every other planet, `neighbour_positions` returns the
+    /// positions next to a position according to the map's topology.
+    pub fn all_planets_connected(planets: &[Vec<Option<Planet>>], neighbour_positions: impl Fn(usize, usize) -> Vec<(usize, usize)>) -> bool {
+        let mut visited: Vec<Vec<bool>> = planets.iter().map(|row| vec![false; row.len()]).collect();
+        let mut visited_planets = 0;
+        let mut queue = VecDeque::new();
+
+        // Finde den ersten vorhandenen Planeten und starte die Suche von dort
+        let existing_planets = planets.iter().flatten().filter(|planet| planet.is_some()).count();
+        if let Some(start) = (0..planets.len()).flat_map(|x| (0..planets[x].len()).map(move |y| (x, y))).find(|&(x, y)| planets[x][y].is_some()) {
+            queue.push_back(start);
+            visited[start.0][start.1] = true;
+            visited_planets += 1;
+        }
+
+        // Führe eine Breitensuche (BFS) durch, um alle erreichbaren Planeten zu besuchen
+        while let Some((x, y)) = queue.pop_front() {
+            for (new_x, new_y) in neighbour_positions(x, y) {
+                if planets[new_x][new_y].is_some() && !visited[new_x][new_y] {
+                    visited[new_x][new_y] = true;
+                    visited_planets += 1;
+                    queue.push_back((new_x, new_y));
                 }
             }
         }
 
-        true
+        // Überprüfe, ob alle Planeten besucht wurden
+        existing_planets == visited_planets
     }
 
+    #[cfg(test)]
     pub fn convert_to_hashmap(planets: &Vec<Vec<Option<Planet>>>) -> HashMap<Uuid, Planet> {
         let mut map = HashMap::new();
         for x in 0..planets.len() {
@@ -230,31 +388,168 @@ The relationship between planets is express as neighbour relation (Direction(Nor
         }
         return 3;
     }
-
-    fn get_resources(size: usize, x: usize, y: usize) -> (Resource,u32){
-        let mut rng = rand::thread_rng();
-        let mut dist = WeightedIndex::new(&[60, 20, 10, 7, 3]).unwrap();
-        //convert usize to int from dist
-        let mut resource = match dist.sample(&mut rng) {
-            0 => Resource::COAL,
-            1 => Resource::IRON,
-            2 => Resource::GEM,
-            3 => Resource::GOLD,
-            4 => Resource::PLATINUM,
-            _ => panic!("Error while generating resources")
-        };
-        (resource, 10000)
-    }
 }
 
 #[cfg(test)]
 mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::planet::map_error::MapError;
+    use crate::planet::map_file::MapFile;
     use crate::planet::map_generator::MapGenerator;
+    use crate::planet::map_symmetry::MapSymmetry;
+    use crate::planet::map_topology::MapTopology;
+    use crate::planet::planet::Planet;
+    use crate::planet::resource::Resource;
+    use crate::planet::resource_profile::ResourceProfile;
+
+    fn assert_neighbours_are_mutual(map: &[Vec<Option<Planet>>]) {
+        let planets = MapGenerator::convert_to_hashmap(&map.to_vec());
+        for planet in planets.values() {
+            for (direction, neighbour_id) in &planet.neighbours {
+                assert_eq!(planets[neighbour_id].neighbours.get(&direction.get_opposite()), Some(&planet.planet_id));
+            }
+        }
+    }
+
+    #[test]
+    fn test_create_map_with_topologies() {
+        for topology in [MapTopology::Grid, MapTopology::Hex, MapTopology::Torus, MapTopology::Islands { islands: 3 }] {
+            let (map, _) = MapGenerator::create_map_with_topology(15, &topology, &MapSymmetry::None, &ResourceProfile::default(), &mut StdRng::seed_from_u64(7)).unwrap();
+            assert_neighbours_are_mutual(&map);
+            let (same_map, _) = MapGenerator::create_map_with_topology(15, &topology, &MapSymmetry::None, &ResourceProfile::default(), &mut StdRng::seed_from_u64(7)).unwrap();
+            assert_eq!(MapGenerator::convert_to_hashmap(&map).keys().collect::<std::collections::HashSet<_>>(),
+                       MapGenerator::convert_to_hashmap(&same_map).keys().collect());
+        }
+        let (hex_map, _) = MapGenerator::create_map_with_topology(15, &MapTopology::Hex, &MapSymmetry::None, &ResourceProfile::default(), &mut StdRng::seed_from_u64(7)).unwrap();
+        assert!(MapGenerator::convert_to_hashmap(&hex_map).values().any(|planet| planet.neighbours.len() == 6));
+        let (torus_map, _) = MapGenerator::create_map_with_topology(15, &MapTopology::Torus, &MapSymmetry::None, &ResourceProfile::default(), &mut StdRng::seed_from_u64(7)).unwrap();
+        assert!(MapGenerator::convert_to_hashmap(&torus_map).values().all(|planet| !planet.neighbours.is_empty()));
+        assert!((0..15).any(|y| torus_map[0][y].as_ref().zip(torus_map[14][y].as_ref())
+            .is_some_and(|(west, east)| west.neighbours.values().any(|id| *id == east.planet_id))));
+        for topology in [MapTopology::Grid, MapTopology::Hex, MapTopology::Torus, MapTopology::Islands { islands: 3 }] {
+            assert!(matches!(MapGenerator::create_map_with_topology(0, &topology, &MapSymmetry::None, &ResourceProfile::default(), &mut StdRng::seed_from_u64(7)),
+                Err(MapError::InvalidMap(_))));
+        }
+    }
+
+    #[test]
+    fn test_locally_removable_planets_keep_the_map_connected() {
+        for topology in [MapTopology::Grid, MapTopology::Hex, MapTopology::Torus] {
+            let (mut map, _) = MapGenerator::create_map_with_topology(12, &topology, &MapSymmetry::None, &ResourceProfile::default(), &mut StdRng::seed_from_u64(5)).unwrap();
+            let adjacent_positions = MapGenerator::get_adjacent_positions(&topology, 12);
+            let mut locally_removable_planets = 0;
+            for x in 0..12 {
+                for y in 0..12 {
+                    if map[x][y].is_some() && MapGenerator::is_locally_removable(&map, &adjacent_positions, x * 12 + y) {
+                        locally_removable_planets += 1;
+                        assert!(MapGenerator::can_remove_planets(&mut map, &[(x, y)], &topology), "{topology:?} ({x}, {y})");
+                    }
+                }
+            }
+            assert!(locally_removable_planets > 100);
+        }
+        assert!(matches!(MapGenerator::create_map_with_topology(1, &MapTopology::Grid, &MapSymmetry::None, &ResourceProfile::default(), &mut StdRng::seed_from_u64(5)),
+            Err(MapError::ObstaclesNotPlaced { placed: 0, wanted: 1 })));
+    }
+
+    /// The benchmark of map generation, run it with `cargo test --release benchmark_create_map -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn benchmark_create_map() {
+        for size in [15, 31, 63, 127, 255] {
+            let start = std::time::Instant::now();
+            MapGenerator::create_map_with_topology(size, &MapTopology::Grid, &MapSymmetry::None, &ResourceProfile::default(), &mut StdRng::seed_from_u64(1)).unwrap();
+            println!("Map of size {size} took {:?}", start.elapsed());
+        }
+    }
+
+    #[test]
+    fn test_resources_follow_the_rings() {
+        let resource_profile = ResourceProfile::default();
+        let ResourceProfile::Rings { outer, middle, inner } = &resource_profile else { unreachable!() };
+        let (map, _) = MapGenerator::create_map_with_topology(31, &MapTopology::Grid, &MapSymmetry::None, &resource_profile, &mut StdRng::seed_from_u64(9)).unwrap();
+        let mut planets_per_ring = [0; 3];
+        let mut deposits_per_ring = [0; 3];
+        for planet in map.iter().flatten().flatten() {
+            let ring_index = planet.movement_difficulty as usize - 1;
+            planets_per_ring[ring_index] += 1;
+            let ring = [outer, middle, inner][ring_index];
+            if let Some((resource, amount)) = &planet.resources {
+                deposits_per_ring[ring_index] += 1;
+                assert!(ring.resources.iter().any(|(ring_resource, _)| ring_resource == resource), "{resource:?} in ring {}", ring_index + 1);
+                assert_eq!(*amount, ring.deposit_size);
+            }
+        }
+        assert!(planets_per_ring.iter().all(|planets| *planets > 0));
+        let density = |ring_index: usize| deposits_per_ring[ring_index] as f64 / planets_per_ring[ring_index] as f64;
+        assert!(density(0) > density(1) && density(1) > density(2));
+
+        let (uniform_map, _) = MapGenerator::create_map_with_topology(31, &MapTopology::Grid, &MapSymmetry::None, &ResourceProfile::Uniform, &mut StdRng::seed_from_u64(9)).unwrap();
+        let uniform_deposits: Vec<_> = uniform_map.iter().flatten().flatten().filter_map(|planet| planet.resources.as_ref().map(|resources| (planet.movement_difficulty, resources))).collect();
+        assert!(uniform_deposits.iter().all(|(_, (_, amount))| *amount == 10000));
+        assert!(uniform_deposits.iter().any(|(movement_difficulty, (resource, _))| *movement_difficulty == 1 && *resource != Resource::COAL));
+
+        let mut invalid_profile = resource_profile.clone();
+        if let ResourceProfile::Rings { inner, .. } = &mut invalid_profile {
+            inner.resources.clear();
+        }
+        assert!(MapGenerator::create_map_with_topology(15, &MapTopology::Grid, &MapSymmetry::None, &invalid_profile, &mut StdRng::seed_from_u64(9)).is_err());
+    }
+
+    #[test]
+    fn test_symmetric_maps() {
+        for folds in [2, 4] {
+            let symmetry = MapSymmetry::Rotational { folds };
+            let (map, spawn_points) = MapGenerator::create_map_with_topology(15, &MapTopology::Grid, &symmetry, &ResourceProfile::default(), &mut StdRng::seed_from_u64(7)).unwrap();
+            assert_eq!(spawn_points.len(), folds as usize);
+            for x in 0..15 {
+                for y in 0..15 {
+                    let images: Vec<_> = symmetry.get_orbit(x, y, 15).into_iter()
+                        .map(|(x, y)| map[x][y].as_ref().map(|planet| (planet.movement_difficulty, planet.resources.clone())))
+                        .collect();
+                    assert!(images.iter().all(|image| *image == images[0]));
+                }
+            }
+        }
+        assert!(MapGenerator::create_map_with_topology(15, &MapTopology::Hex, &MapSymmetry::Rotational { folds: 2 }, &ResourceProfile::default(), &mut StdRng::seed_from_u64(7)).is_err());
+        assert!(MapGenerator::create_map_with_topology(15, &MapTopology::Grid, &MapSymmetry::Rotational { folds: 3 }, &ResourceProfile::default(), &mut StdRng::seed_from_u64(7)).is_err());
+    }
+
+    #[test]
+    fn test_map_file() {
+        let map_file: MapFile = serde_json::from_str(r#"{"planets": [
+            {"id": "a", "x": 0, "y": 0, "movement_difficulty": 1, "neighbours": {"EAST": "b"}},
+            {"id": "b", "x": 1, "y": 0, "movement_difficulty": 2, "resources": ["IRON", 500]},
+            {"id": "c", "x": 3, "y": 3, "movement_difficulty": 3}
+        ]}"#).unwrap();
+        assert!(map_file.to_planets(&mut StdRng::seed_from_u64(7)).is_err());
+
+        let mut connected_map_file = map_file.clone();
+        connected_map_file.planets[2].neighbours.insert(crate::planet::direction::Direction::NORTH, "b".to_string());
+        connected_map_file.spawn_points = vec!["a".to_string(), "c".to_string()];
+        let (map, spawn_points) = connected_map_file.to_planets(&mut StdRng::seed_from_u64(7)).unwrap();
+        assert_eq!(map.len(), 4);
+        assert_neighbours_are_mutual(&map);
+        assert_eq!(spawn_points, vec![map[0][0].as_ref().unwrap().planet_id, map[3][3].as_ref().unwrap().planet_id]);
+
+        connected_map_file.version = MapFile::CURRENT_VERSION + 1;
+        assert!(connected_map_file.to_planets(&mut StdRng::seed_from_u64(7)).is_err());
+
+        let mut oversized_map_file = map_file.clone();
+        oversized_map_file.planets.truncate(1);
+        oversized_map_file.planets[0].neighbours.clear();
+        oversized_map_file.planets[0].x = 4_000_000_000;
+        assert!(matches!(oversized_map_file.to_planets(&mut StdRng::seed_from_u64(7)), Err(MapError::InvalidMap(_))));
+        oversized_map_file.planets[0].x = MapFile::MAX_SIZE - 1;
+        assert!(oversized_map_file.to_planets(&mut StdRng::seed_from_u64(7)).is_ok());
+    }
 
     #[test]
     fn test_create_map() {
         let size = 15;
-        let map = MapGenerator::create_map(size);
+        let map = MapGenerator::create_map(size).unwrap();
         for row in map {
             assert_eq!(row.len(), size);
         }
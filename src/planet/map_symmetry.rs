@@ -0,0 +1,35 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+/// Whether the generated map is mirrored, so every player starts with the same surroundings.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum MapSymmetry {
+    /// Holes and resources are placed independently at random.
+    #[default]
+    None,
+    /// The map is made of `folds` identical sectors which are rotated around the center, one per player.
+    /// 2 folds make the map point-symmetric, 4 folds turn every sector by a quarter.
+    Rotational { folds: u8 },
+}
+
+impl MapSymmetry {
+    /// Returns the position and its images in the other sectors of a `size` x `size` map, starting with the position
+    /// itself and ordered by sector. Positions at the center can be their own image, they are only returned once.
+    pub fn get_orbit(&self, x: usize, y: usize, size: usize) -> Vec<(usize, usize)> {
+        let folds = match self {
+            MapSymmetry::None => 1,
+            MapSymmetry::Rotational { folds } => *folds,
+        };
+        let mut orbit = vec![(x, y)];
+        let mut seen = BTreeSet::from([(x, y)]);
+        for _ in 1..folds {
+            let &(previous_x, previous_y) = orbit.last().unwrap();
+            let next = if folds == 2 { (size - 1 - previous_x, size - 1 - previous_y) } else { (size - 1 - previous_y, previous_x) };
+            if seen.insert(next) {
+                orbit.push(next);
+            }
+        }
+        orbit
+    }
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+use crate::planet::direction::Direction;
+
+/// Shape of the map and how its planets are connected, chosen when a game is created.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum MapTopology {
+    /// Square grid, every planet has up to four neighbours.
+    #[default]
+    Grid,
+    /// Grid of hexagons in columns, every planet has up to six neighbours.
+    Hex,
+    /// Square grid whose edges wrap around, so planets on opposite edges are neighbours.
+    Torus,
+    /// Round islands of planets, joined by narrow corridors.
+    Islands { islands: u8 },
+    /// A map loaded from `{name}.json` in the maps directory, see `MapFile`.
+    File { name: String },
+}
+
+impl MapTopology {
+    /// Returns the direction and position of every position next to (x, y) in a grid of `size` x `size` positions.
+    /// Maps loaded from a file define their neighbours themselves, they are treated like a square grid here.
+    pub fn get_neighbour_positions(&self, x: usize, y: usize, size: usize) -> Vec<(Direction, usize, usize)> {
+        let offsets: Vec<(Direction, isize, isize)> = match self {
+            MapTopology::Hex if x.is_multiple_of(2) => vec![
+                (Direction::NORTH, 0, -1), (Direction::SOUTH, 0, 1),
+                (Direction::NORTHEAST, 1, -1), (Direction::SOUTHEAST, 1, 0),
+                (Direction::SOUTHWEST, -1, 0), (Direction::NORTHWEST, -1, -1),
+            ],
+            MapTopology::Hex => vec![
+                (Direction::NORTH, 0, -1), (Direction::SOUTH, 0, 1),
+                (Direction::NORTHEAST, 1, 0), (Direction::SOUTHEAST, 1, 1),
+                (Direction::SOUTHWEST, -1, 1), (Direction::NORTHWEST, -1, 0),
+            ],
+            _ => vec![(Direction::WEST, -1, 0), (Direction::EAST, 1, 0), (Direction::NORTH, 0, -1), (Direction::SOUTH, 0, 1)],
+        };
+        let wraps_around = *self == MapTopology::Torus;
+        offsets.into_iter().filter_map(|(direction, dx, dy)| {
+            let (new_x, new_y) = (x as isize + dx, y as isize + dy);
+            if wraps_around {
+                return Some((direction, new_x.rem_euclid(size as isize) as usize, new_y.rem_euclid(size as isize) as usize));
+            }
+            if new_x < 0 || new_y < 0 || new_x >= size as isize || new_y >= size as isize {
+                return None;
+            }
+            Some((direction, new_x as usize, new_y as usize))
+        }).collect()
+    }
+}
@@ -2,3 +2,11 @@ pub mod direction;
 pub mod planet;
 pub mod resource;
 pub mod map_generator;
+pub mod pathfinding;
+pub mod planet_memory;
+pub mod map_topology;
+pub mod map_error;
+pub mod map_file;
+pub mod map_symmetry;
+pub mod map_fairness;
+pub mod resource_profile;
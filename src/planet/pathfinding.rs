@@ -0,0 +1,109 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::game::game_rules::MovementCostModel;
+use crate::game::game_state::GameMap;
+
+/// The cheapest route between two planets, including the start and the destination.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Path {
+    pub planets: Vec<Uuid>,
+    pub energy_cost: u32,
+}
+
+/// Finds the route which costs the least energy to move along.
+/// With `known_planets`, only the neighbours of these planets are explored, because a player doesn't know
+/// where they can go from a planet they have never seen. Without it, the whole map is searched.
+pub fn find_cheapest_path(map: &GameMap, movement_cost_model: &MovementCostModel, known_planets: Option<&HashSet<Uuid>>, from: Uuid, to: Uuid) -> Option<Path> {
+    let mut energy_costs: HashMap<Uuid, u32> = HashMap::from([(from, 0)]);
+    let mut previous_planets: HashMap<Uuid, Uuid> = HashMap::new();
+    let mut queue = BinaryHeap::from([Reverse((0, from))]);
+
+    while let Some(Reverse((energy_cost, planet_id))) = queue.pop() {
+        if planet_id == to {
+            let mut planets = vec![to];
+            while let Some(previous_planet_id) = previous_planets.get(planets.last().unwrap()) {
+                planets.push(*previous_planet_id);
+            }
+            planets.reverse();
+            return Some(Path { planets, energy_cost });
+        }
+        if energy_cost > energy_costs[&planet_id] || known_planets.is_some_and(|known_planets| !known_planets.contains(&planet_id)) {
+            continue;
+        }
+        let Some(planet) = map.get_planet(&planet_id) else { continue };
+        for neighbour_id in planet.neighbours.values() {
+            let Some(neighbour) = map.get_planet(neighbour_id) else { continue };
+            let neighbour_energy_cost = energy_cost + movement_cost_model.get_movement_cost(planet, neighbour);
+            if energy_costs.get(neighbour_id).is_none_or(|known_cost| neighbour_energy_cost < *known_cost) {
+                energy_costs.insert(*neighbour_id, neighbour_energy_cost);
+                previous_planets.insert(*neighbour_id, planet_id);
+                queue.push(Reverse((neighbour_energy_cost, *neighbour_id)));
+            }
+        }
+    }
+    None
+}
+
+/// Returns the energy it costs to move from `from` to every planet which can be reached from it.
+/// Like in `find_cheapest_path`, only the neighbours of `known_planets` are explored, if they are given.
+pub fn get_energy_costs_from(map: &GameMap, movement_cost_model: &MovementCostModel, known_planets: Option<&HashSet<Uuid>>, from: Uuid) -> HashMap<Uuid, u32> {
+    let mut energy_costs: HashMap<Uuid, u32> = HashMap::from([(from, 0)]);
+    let mut queue = BinaryHeap::from([Reverse((0, from))]);
+    while let Some(Reverse((energy_cost, planet_id))) = queue.pop() {
+        if energy_cost > energy_costs[&planet_id] || known_planets.is_some_and(|known_planets| !known_planets.contains(&planet_id)) {
+            continue;
+        }
+        let Some(planet) = map.get_planet(&planet_id) else { continue };
+        for neighbour_id in planet.neighbours.values() {
+            let Some(neighbour) = map.get_planet(neighbour_id) else { continue };
+            let neighbour_energy_cost = energy_cost + movement_cost_model.get_movement_cost(planet, neighbour);
+            if energy_costs.get(neighbour_id).is_none_or(|known_cost| neighbour_energy_cost < *known_cost) {
+                energy_costs.insert(*neighbour_id, neighbour_energy_cost);
+                queue.push(Reverse((neighbour_energy_cost, *neighbour_id)));
+            }
+        }
+    }
+    energy_costs
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::game_rules::GameRules;
+    use crate::game::game_state::GameState;
+    use crate::planet::direction::Direction;
+    use crate::planet::planet::Planet;
+
+    use super::*;
+
+    #[test]
+    fn test_find_cheapest_path() {
+        // a - b - d
+        // |       |
+        // c ----- e
+        let mut planets: Vec<Planet> = [1, 5, 1, 1, 1].iter().map(|movement_difficulty| Planet::new(Uuid::new_v4(), *movement_difficulty)).collect();
+        let ids: Vec<Uuid> = planets.iter().map(|planet| planet.planet_id).collect();
+        for (from, to, direction) in [(0, 1, Direction::EAST), (1, 3, Direction::EAST), (0, 2, Direction::SOUTH), (2, 4, Direction::EAST), (4, 3, Direction::NORTH)] {
+            planets[from].set_neighbour(direction.clone(), ids[to]);
+            planets[to].set_neighbour(direction.get_opposite(), ids[from]);
+        }
+        let game_state = GameState::new(Uuid::new_v4(), 1, 1, vec![planets.into_iter().map(Some).collect()], GameRules::default(), 0);
+        let map = &game_state.round_states[&0].map;
+
+        let path = find_cheapest_path(map, &MovementCostModel::Source, None, ids[0], ids[3]).unwrap();
+        assert_eq!(path, Path { planets: vec![ids[0], ids[2], ids[4], ids[3]], energy_cost: 3 });
+
+        let known_planets = HashSet::from([ids[0], ids[1]]);
+        let path = find_cheapest_path(map, &MovementCostModel::Source, Some(&known_planets), ids[0], ids[3]).unwrap();
+        assert_eq!(path, Path { planets: vec![ids[0], ids[1], ids[3]], energy_cost: 6 });
+        assert_eq!(find_cheapest_path(map, &MovementCostModel::Source, Some(&known_planets), ids[0], ids[4]), None);
+
+        // Entering b is expensive, leaving it is not
+        assert_eq!(find_cheapest_path(map, &MovementCostModel::Destination, None, ids[0], ids[1]).unwrap().energy_cost, 5);
+        assert_eq!(find_cheapest_path(map, &MovementCostModel::Destination, None, ids[1], ids[0]).unwrap().energy_cost, 1);
+        assert_eq!(find_cheapest_path(map, &MovementCostModel::Max, None, ids[1], ids[0]).unwrap().energy_cost, 5);
+    }
+}
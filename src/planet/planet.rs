@@ -12,6 +12,8 @@ pub struct Planet {
     pub movement_difficulty: u8,
     pub resources: Option<(Resource, u32)>,
     pub neighbours: HashMap<Direction, Uuid>,
+    #[serde(default)]
+    pub loot: HashMap<Resource, u32>, // Dropped by robots which died on this planet
 }
 
 impl Planet {
@@ -21,6 +23,7 @@ impl Planet {
             movement_difficulty,
             resources: None,
             neighbours: HashMap::new(),
+            loot: HashMap::new(),
         }
     }
 
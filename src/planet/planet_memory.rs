@@ -0,0 +1,24 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::planet::planet::Planet;
+use crate::planet::resource::Resource;
+
+/// What a player saw on a planet, the last time it was in sight of one of their robots or an allied robot.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PlanetMemory {
+    pub last_seen_round: u16,
+    pub resources: Option<(Resource, u32)>,
+    pub loot: HashMap<Resource, u32>,
+}
+
+impl PlanetMemory {
+    pub fn new(planet: &Planet, round_number: u16) -> PlanetMemory {
+        PlanetMemory {
+            last_seen_round: round_number,
+            resources: planet.resources.clone(),
+            loot: planet.loot.clone(),
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::planet::map_error::MapError;
+use crate::planet::resource::Resource;
+
+/// Decides which resources the generated planets contain, chosen when a game is created.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ResourceProfile {
+    /// Resources depend on the ring of the planet, which is given by its movement difficulty.
+    Rings { outer: RingResources, middle: RingResources, inner: RingResources },
+    /// 80% of all planets contain 10000 units of a resource, chosen with the same weights everywhere.
+    Uniform,
+}
+
+/// The resources of the planets in one ring of the map.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RingResources {
+    pub resources: Vec<(Resource, u32)>, // Resource -> Weight with which it is chosen
+    pub density: u8, // Percentage of the planets which contain a resource
+    pub deposit_size: u32,
+}
+
+impl Default for ResourceProfile {
+    /// Coal in the outer ring, iron and gems in the middle ring, gold and platinum in the inner ring.
+    /// Deposits get smaller and rarer towards the center, but are worth more.
+    fn default() -> Self {
+        ResourceProfile::Rings {
+            outer: RingResources { resources: vec![(Resource::COAL, 1)], density: 80, deposit_size: 10000 },
+            middle: RingResources { resources: vec![(Resource::IRON, 2), (Resource::GEM, 1)], density: 60, deposit_size: 6000 },
+            inner: RingResources { resources: vec![(Resource::GOLD, 3), (Resource::PLATINUM, 2)], density: 40, deposit_size: 3000 },
+        }
+    }
+}
+
+impl ResourceProfile {
+    pub fn validate(&self) -> Result<(), MapError> {
+        let ResourceProfile::Rings { outer, middle, inner } = self else { return Ok(()) };
+        for (name, ring) in [("outer", outer), ("middle", middle), ("inner", inner)] {
+            if ring.density > 100 {
+                return Err(MapError::InvalidMap(format!("The density of the {name} ring is more than 100 percent")));
+            }
+            if ring.density > 0 && ring.resources.iter().all(|(_, weight)| *weight == 0) {
+                return Err(MapError::InvalidMap(format!("The {name} ring has no resources to choose from")));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rolls the resources of a planet with the given movement difficulty, which is 1 in the outer ring and 3 in the
+    /// inner ring. The profile has to be valid.
+    pub fn get_resources(&self, movement_difficulty: u8, rng: &mut impl Rng) -> Option<(Resource, u32)> {
+        let ring = match self {
+            ResourceProfile::Uniform => &RingResources {
+                resources: vec![(Resource::COAL, 60), (Resource::IRON, 20), (Resource::GEM, 10), (Resource::GOLD, 7), (Resource::PLATINUM, 3)],
+                density: 80,
+                deposit_size: 10000,
+            },
+            ResourceProfile::Rings { outer, middle, inner } => match movement_difficulty {
+                0 | 1 => outer,
+                2 => middle,
+                _ => inner,
+            },
+        };
+        if rng.gen_range(0..100) >= ring.density {
+            return None;
+        }
+        let distribution = WeightedIndex::new(ring.resources.iter().map(|(_, weight)| *weight)).expect("Resource profile was not validated");
+        Some((ring.resources[distribution.sample(rng)].0.clone(), ring.deposit_size))
+    }
+}
@@ -3,20 +3,76 @@ use std::collections::{HashMap, HashSet, VecDeque};
 use serde::{Deserialize, Serialize, Serializer};
 use uuid::Uuid;
 
+use crate::bot::bot_strategy::BotStrategy;
+use crate::planet::planet_memory::PlanetMemory;
+use crate::robot::enemy_intel::EnemyIntel;
 use crate::robot::robot::Robot;
 use crate::trading::external::command::Command;
+use crate::trading::external::command_rejection::CommandRejection;
 use crate::trading::external::command_type::CommandType;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PlayerState {
     pub player_name: String,
+    #[serde(default)]
+    pub status: PlayerStatus,
     pub money: Money,
     pub total_money_made: Money,
     pub visited_planets: HashSet<Uuid>,
     #[serde(serialize_with = "sort_and_serialize_robots")]
     pub robots : HashMap<Uuid,Robot>,
     pub commands: HashMap<CommandType, VecDeque<Command>>,
-    pub killed_robots : HashMap<Uuid,Vec<(String,Robot)>> // OurRobotId -> Enemy_player_name, Enemy_robot
+    pub killed_robots : HashMap<Uuid,Vec<(String,Robot)>>, // OurRobotId -> Enemy_player_name, Enemy_robot
+    #[serde(default)]
+    pub assisted_kills : HashMap<Uuid,Vec<(String,Robot)>>, // OurRobotId -> Enemy_player_name, Enemy_robot killed by another robot
+    #[serde(default)]
+    pub rejected_commands: Vec<CommandRejection>, // Commands of the last processed round which were not executed
+    #[serde(default)]
+    pub eliminated_in_round: Option<u16>, // Round in which the player had no robots left and could not afford a new one
+    #[serde(default)]
+    pub planet_memories: HashMap<Uuid, PlanetMemory>, // Planets which were in sight before, only kept when playing with fog of war
+    #[serde(default)]
+    pub enemy_intel: HashMap<Uuid, EnemyIntel>, // EnemyRobotId -> Last sighting of the alive enemy robot
+    #[serde(default)]
+    pub submitted_commands: bool, // Whether the player submitted a batch of commands in the current round
+    #[serde(default)]
+    pub ended_turn: bool, // Whether the player sent END_TURN in the current round
+    #[serde(default)]
+    pub home_planet: Option<Uuid>, // Spawn point on which the player's robots spawn, if the map has one left for them
+    #[serde(default)]
+    pub bot: Option<BotStrategy>, // Strategy of a built-in bot, whose commands are queued by the server every round
+}
+
+impl PlayerState {
+    pub fn new(player_name: String, starting_money: u32) -> PlayerState {
+        PlayerState {
+            player_name,
+            status: PlayerStatus::Active,
+            money: Money { amount: starting_money },
+            total_money_made: Money { amount: starting_money },
+            visited_planets: HashSet::new(),
+            commands: vec![
+                (CommandType::SELLING, VecDeque::new()),
+                (CommandType::BUYING, VecDeque::new()),
+                (CommandType::MOVEMENT, VecDeque::new()),
+                (CommandType::BATTLE, VecDeque::new()),
+                (CommandType::MINING, VecDeque::new()),
+                (CommandType::REGENERATE, VecDeque::new()),
+                (CommandType::PICKUP, VecDeque::new()),
+            ].into_iter().collect(),
+            robots: HashMap::new(),
+            killed_robots: HashMap::new(),
+            assisted_kills: HashMap::new(),
+            rejected_commands: Vec::new(),
+            eliminated_in_round: None,
+            planet_memories: HashMap::new(),
+            enemy_intel: HashMap::new(),
+            submitted_commands: false,
+            ended_turn: false,
+            home_planet: None,
+            bot: None,
+        }
+    }
 }
 
 fn sort_and_serialize_robots<S>(
@@ -35,6 +91,19 @@ fn sort_and_serialize_robots<S>(
     sorted_robots.serialize(serializer)
 }
 
+/// Only active players take part in the rounds. All other players can still watch the game.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+pub enum PlayerStatus {
+    #[default]
+    Active,
+    /// The player has no robots left and cannot afford a new one.
+    Eliminated,
+    /// The player did not submit commands in time. Submitting commands again makes the player active.
+    Disconnected,
+    /// The player left the game.
+    Forfeited,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Money{
     pub amount: u32,
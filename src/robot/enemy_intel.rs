@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::robot::robot::Robot;
+use crate::robot::robot_levels::RobotLevels;
+
+/// What a player knows about an enemy robot, from the last round it was in sight of one of their robots or an allied robot.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EnemyIntel {
+    pub player_name: String,
+    pub planet_id: Uuid,
+    pub last_seen_round: u16,
+    pub levels: RobotLevels,
+    pub health: u32,
+}
+
+impl EnemyIntel {
+    pub fn new(player_name: &str, robot: &Robot, round_number: u16) -> EnemyIntel {
+        EnemyIntel {
+            player_name: player_name.to_string(),
+            planet_id: robot.planet_id,
+            last_seen_round: round_number,
+            levels: robot.levels.clone(),
+            health: robot.health,
+        }
+    }
+}
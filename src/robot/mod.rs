@@ -3,4 +3,6 @@ pub mod robot;
 pub mod robot_level;
 
 pub mod robot_levels;
-pub mod robot_stats;
\ No newline at end of file
+pub mod robot_stats;
+pub mod standing_order;
+pub mod enemy_intel;
@@ -7,6 +7,7 @@ use uuid::Uuid;
 use crate::planet::resource::Resource;
 use crate::robot::robot_levels::RobotLevels;
 use crate::robot::robot_stats::RobotStats;
+use crate::robot::standing_order::StandingOrder;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Robot {
@@ -18,6 +19,8 @@ pub struct Robot {
     pub stats: RobotStats,
     pub inventory: HashMap<Resource, u32>,
     pub money_made: u32,
+    #[serde(default)]
+    pub standing_order: Option<StandingOrder>,
 }
 
 
@@ -33,6 +36,7 @@ impl Robot {
             levels,
             inventory: HashMap::new(),
             money_made: 0,
+            standing_order: None,
         }
     }
     pub fn is_alive(&self) -> bool {
@@ -20,6 +20,18 @@ impl RobotLevel {
             RobotLevel::LEVEL5 => 5,
         }
     }
+
+    /// Returns the level an upgrade leads to, or None if this is the highest level.
+    pub fn next(&self) -> Option<RobotLevel> {
+        match self {
+            RobotLevel::LEVEL0 => Some(RobotLevel::LEVEL1),
+            RobotLevel::LEVEL1 => Some(RobotLevel::LEVEL2),
+            RobotLevel::LEVEL2 => Some(RobotLevel::LEVEL3),
+            RobotLevel::LEVEL3 => Some(RobotLevel::LEVEL4),
+            RobotLevel::LEVEL4 => Some(RobotLevel::LEVEL5),
+            RobotLevel::LEVEL5 => None,
+        }
+    }
 }
 
 impl Default for RobotLevel {
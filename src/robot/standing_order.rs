@@ -0,0 +1,19 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// An order which stays on a robot over several rounds. Every round in which the robot has no other command,
+/// the order is turned into a single command, until the order is complete or cancelled.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum StandingOrder {
+    /// Move along the given planets, one planet per round. The robot regenerates in rounds where it lacks the energy to move.
+    FollowPath { path: VecDeque<Uuid> },
+    /// Move towards the destination along the cheapest known route, which is planned again every round.
+    /// The robot regenerates in rounds where it lacks the energy to move. Given to a robot by a `MOVE_TO` command.
+    MoveTo { destination: Uuid },
+    /// Mine on the current planet until the storage is full, then sell everything. Repeats until nothing can be mined there anymore.
+    MineThenSell,
+    /// Regenerate until the energy is full.
+    RegenerateUntilFull,
+}
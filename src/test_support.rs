@@ -0,0 +1,29 @@
+use uuid::Uuid;
+
+pub use crate::api::games::STARTING_MONEY;
+use crate::game::game_rules::GameRules;
+use crate::game::game_state::GameState;
+use crate::planet::planet::Planet;
+use crate::player::PlayerState;
+use crate::robot::robot::Robot;
+
+/// Creates a game whose planets lie in a single column, with the given players and their robots in round 0.
+pub fn create_game_state(players: Vec<(&str, Vec<Robot>)>, planets: Vec<Planet>, rules: GameRules) -> GameState {
+    let mut game_state = GameState::new(Uuid::new_v4(), 10, players.len().max(1) as u8, vec![planets.into_iter().map(Some).collect()], rules, 0);
+    add_players(&mut game_state, players);
+    game_state
+}
+
+/// Adds the players with their robots to round 0, every player starts with `STARTING_MONEY`.
+pub fn add_players(game_state: &mut GameState, players: Vec<(&str, Vec<Robot>)>) {
+    for (player_name, robots) in players {
+        let mut player = PlayerState::new(player_name.to_string(), STARTING_MONEY);
+        player.robots = robots.into_iter().map(|robot| (robot.robot_id, robot)).collect();
+        game_state.participating_players.push(player_name.to_string());
+        game_state.round_states.get_mut(&0).unwrap().player_name_player_map.insert(player_name.to_string(), player);
+    }
+}
+
+pub fn create_robots(amount: usize, planet_id: Uuid) -> Vec<Robot> {
+    (0..amount).map(|_| Robot::new(Uuid::new_v4(), planet_id)).collect()
+}
@@ -0,0 +1,3 @@
+pub mod tournament;
+pub mod tournament_format;
+pub mod tournament_error;
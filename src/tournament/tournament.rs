@@ -0,0 +1,347 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::games::CreateGameRequestBody;
+use crate::bot::bot_strategy::BotStrategy;
+use crate::game::game_results::GameResults;
+use crate::game::game_rules::TurnGate;
+use crate::tournament::tournament_error::TournamentError;
+use crate::tournament::tournament_format::TournamentFormat;
+
+const INITIAL_RATING: f64 = 1500.0;
+const RATING_FACTOR: f64 = 32.0; // Most rating points an agent can win or lose in a single game
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tournament {
+    pub tournament_id: Uuid,
+    pub status: TournamentStatus,
+    pub format: TournamentFormat,
+    pub seeds: Vec<u64>, // Every pairing plays one game on each seed
+    pub game: CreateGameRequestBody, // Settings of the tournament's games, their seeds are taken from `seeds`
+    pub agents: Vec<Agent>,
+    pub current_round: u16,
+    pub matches: Vec<TournamentMatch>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum TournamentStatus {
+    Created,
+    Running,
+    Ended,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Agent {
+    pub name: String,
+    pub bot: Option<BotStrategy>, // None for an external agent, which plays its games through the API like any player
+    pub rating: f64, // Elo rating
+    pub points: f64, // 1 for every win and bye, 0.5 for every draw
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub byes: u32,
+}
+
+/// One game between two agents.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TournamentMatch {
+    pub round: u16,
+    pub seed: u64,
+    pub agents: [String; 2], // In the order in which they join the game, which decides their home planets
+    pub game_id: Option<Uuid>, // Set once the game was created
+    pub result: Option<MatchResult>, // Set once the game has ended
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum MatchResult {
+    Winner(String),
+    Draw,
+}
+
+impl Tournament {
+    pub fn new(format: TournamentFormat, seeds: Vec<u64>, game: CreateGameRequestBody) -> Result<Tournament, TournamentError> {
+        if seeds.is_empty() {
+            return Err(TournamentError::NoSeeds);
+        }
+        if format.get_rounds() == 0 {
+            return Err(TournamentError::NoRounds);
+        }
+        Ok(Tournament {
+            tournament_id: Uuid::new_v4(),
+            status: TournamentStatus::Created,
+            format,
+            seeds,
+            game,
+            agents: Vec::new(),
+            current_round: 0,
+            matches: Vec::new(),
+        })
+    }
+
+    pub fn add_agent(&mut self, name: &str, bot: Option<BotStrategy>) -> Result<(), TournamentError> {
+        if self.status != TournamentStatus::Created {
+            return Err(TournamentError::AlreadyStarted);
+        }
+        if self.get_agent(name).is_some() {
+            return Err(TournamentError::AgentAlreadyRegistered(name.to_string()));
+        }
+        self.agents.push(Agent {
+            name: name.to_string(),
+            bot,
+            rating: INITIAL_RATING,
+            points: 0.0,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            byes: 0,
+        });
+        Ok(())
+    }
+
+    pub fn get_agent(&self, name: &str) -> Option<&Agent> {
+        self.agents.iter().find(|agent| agent.name == name)
+    }
+
+    /// Pairs the agents for the next round and schedules one match per pairing and seed. Ends the tournament after the last round.
+    pub fn start_next_round(&mut self) -> Result<(), TournamentError> {
+        if self.status == TournamentStatus::Created {
+            if self.agents.len() < 2 {
+                return Err(TournamentError::NotEnoughAgents(self.agents.len()));
+            }
+            // Built-in bots always submit their commands, but an external agent could leave a game waiting forever
+            let has_timeout = matches!(self.game.get_rules().turn_gate, TurnGate::Timeout { .. });
+            if !has_timeout && self.agents.iter().any(|agent| agent.bot.is_none()) {
+                return Err(TournamentError::NoTimeoutForExternalAgents);
+            }
+            self.status = TournamentStatus::Running;
+        }
+        if self.current_round >= self.format.get_rounds() {
+            self.status = TournamentStatus::Ended;
+            return Ok(());
+        }
+        self.current_round += 1;
+        let ranked_agents: Vec<&str> = self.get_standings().into_iter().map(|agent| agent.name.as_str()).collect();
+        let (pairings, bye) = self.format.get_pairings(&ranked_agents, |agent, opponent| self.have_played(agent, opponent),
+                                                       |agent| self.get_agent(agent).is_some_and(|agent| agent.byes > 0));
+        if let Some(agent) = bye.and_then(|bye| self.agents.iter_mut().find(|agent| agent.name == bye)) {
+            agent.byes += 1;
+            agent.points += 1.0;
+        }
+        for (seed_index, seed) in self.seeds.iter().enumerate() {
+            for (agent, opponent) in &pairings {
+                // The agents take turns in joining first
+                let agents = if seed_index % 2 == 0 { [agent.clone(), opponent.clone()] } else { [opponent.clone(), agent.clone()] };
+                self.matches.push(TournamentMatch { round: self.current_round, seed: *seed, agents, game_id: None, result: None });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_current_round_finished(&self) -> bool {
+        self.matches.iter().filter(|tournament_match| tournament_match.round == self.current_round).all(|tournament_match| tournament_match.result.is_some())
+    }
+
+    /// Records the result of a match whose game has ended and updates the points and ratings of both agents.
+    /// The agent ranked higher in the game wins, on equal rank it is a draw.
+    pub fn record_result(&mut self, match_index: usize, results: &GameResults) {
+        let get_rank = |agent: &str| results.standings.iter().find(|standing| standing.player_name == agent).map(|standing| standing.rank).unwrap_or(u16::MAX);
+        let [first, second] = &self.matches[match_index].agents;
+        // Less means that the first agent ranked higher
+        let comparison = get_rank(first).cmp(&get_rank(second));
+        self.record_comparison(match_index, comparison);
+    }
+
+    /// Records a match whose game no longer exists, e.g. because it was deleted, as a draw.
+    pub fn record_missing_game(&mut self, match_index: usize) {
+        self.record_comparison(match_index, Ordering::Equal);
+    }
+
+    fn record_comparison(&mut self, match_index: usize, comparison: Ordering) {
+        let tournament_match = &mut self.matches[match_index];
+        if tournament_match.result.is_some() {
+            return;
+        }
+        let [first, second] = tournament_match.agents.clone();
+        tournament_match.result = Some(match comparison {
+            Ordering::Less => MatchResult::Winner(first.clone()),
+            Ordering::Equal => MatchResult::Draw,
+            Ordering::Greater => MatchResult::Winner(second.clone()),
+        });
+
+        let (Some(first_rating), Some(second_rating)) = (self.get_agent(&first).map(|agent| agent.rating), self.get_agent(&second).map(|agent| agent.rating)) else { return };
+        let rating_change = RATING_FACTOR * (get_score(comparison) - get_expected_score(first_rating, second_rating));
+        for (name, comparison, rating_change) in [(first, comparison, rating_change), (second, comparison.reverse(), -rating_change)] {
+            let Some(agent) = self.agents.iter_mut().find(|agent| agent.name == name) else { continue };
+            agent.rating += rating_change;
+            agent.points += get_score(comparison);
+            match comparison {
+                Ordering::Less => agent.wins += 1,
+                Ordering::Equal => agent.draws += 1,
+                Ordering::Greater => agent.losses += 1,
+            }
+        }
+    }
+
+    /// Returns the agents ordered by points, then by rating.
+    pub fn get_standings(&self) -> Vec<&Agent> {
+        let mut standings: Vec<&Agent> = self.agents.iter().collect();
+        standings.sort_by(|agent, other_agent| other_agent.points.total_cmp(&agent.points)
+            .then(other_agent.rating.total_cmp(&agent.rating))
+            .then(agent.name.cmp(&other_agent.name)));
+        standings
+    }
+
+    fn have_played(&self, agent: &str, opponent: &str) -> bool {
+        self.matches.iter().any(|tournament_match| tournament_match.agents.iter().any(|name| name == agent) && tournament_match.agents.iter().any(|name| name == opponent))
+    }
+}
+
+/// 1 for a win, 0.5 for a draw and 0 for a loss. `Ordering::Less` means that the agent ranked higher than its opponent.
+fn get_score(comparison: Ordering) -> f64 {
+    match comparison {
+        Ordering::Less => 1.0,
+        Ordering::Equal => 0.5,
+        Ordering::Greater => 0.0,
+    }
+}
+
+/// The score an agent is expected to make against the opponent, between 0 for a sure loss and 1 for a sure win.
+fn get_expected_score(rating: f64, opponent_rating: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent_rating - rating) / 400.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::game_rules::GameRules;
+    use crate::planet::planet::Planet;
+    use crate::test_support::create_game_state;
+
+    use super::*;
+
+    fn create_tournament(format: TournamentFormat, agent_names: &[&str]) -> Tournament {
+        let game = serde_json::from_value(serde_json::json!({
+            "max_rounds": 10, "max_players": 2, "map_size": 10, "rules": { "turn_gate": { "Timeout": { "seconds": 30 } } },
+        })).unwrap();
+        let mut tournament = Tournament::new(format, vec![1, 2], game).unwrap();
+        for name in agent_names {
+            tournament.add_agent(name, None).unwrap();
+        }
+        tournament
+    }
+
+    fn get_results(total_money_made: [(&str, u32); 2]) -> GameResults {
+        let players = total_money_made.iter().map(|(name, _)| (*name, vec![])).collect();
+        let mut game_state = create_game_state(players, vec![Planet::new(Uuid::new_v4(), 1)], GameRules::default());
+        for (name, money) in total_money_made {
+            game_state.get_player_for_current_round_as_mut(name).unwrap().total_money_made.amount = money;
+        }
+        GameResults::from_game_state(&game_state)
+    }
+
+    /// Lets the agent which comes first alphabetically win every game of the current round.
+    fn play_current_round(tournament: &mut Tournament) {
+        for match_index in 0..tournament.matches.len() {
+            let tournament_match = &tournament.matches[match_index];
+            if tournament_match.round != tournament.current_round {
+                continue;
+            }
+            let mut agents = tournament_match.agents.clone();
+            agents.sort();
+            let results = get_results([(&agents[0], 900), (&agents[1], 500)]);
+            tournament.record_result(match_index, &results);
+        }
+    }
+
+    #[test]
+    fn test_round_robin_plays_every_pairing_on_every_seed() {
+        let mut tournament = create_tournament(TournamentFormat::RoundRobin, &["a", "b", "c"]);
+        tournament.start_next_round().unwrap();
+        assert_eq!(tournament.matches.len(), 6);
+        assert!(tournament.matches.iter().any(|tournament_match| tournament_match.agents == ["a", "b"]));
+        assert!(tournament.matches.iter().any(|tournament_match| tournament_match.agents == ["b", "a"]));
+        play_current_round(&mut tournament);
+        assert!(tournament.is_current_round_finished());
+        tournament.start_next_round().unwrap();
+        assert_eq!(tournament.status, TournamentStatus::Ended);
+        let standings: Vec<(&str, f64)> = tournament.get_standings().iter().map(|agent| (agent.name.as_str(), agent.points)).collect();
+        assert_eq!(standings, vec![("a", 4.0), ("b", 2.0), ("c", 0.0)]);
+    }
+
+    #[test]
+    fn test_swiss_rounds_avoid_rematches_and_repeated_byes() {
+        let mut tournament = create_tournament(TournamentFormat::Swiss { rounds: 3 }, &["a", "b", "c", "d", "e"]);
+        for _ in 0..3 {
+            tournament.start_next_round().unwrap();
+            assert_eq!(tournament.matches.iter().filter(|tournament_match| tournament_match.round == tournament.current_round).count(), 4);
+            play_current_round(&mut tournament);
+        }
+        tournament.start_next_round().unwrap();
+        assert_eq!(tournament.status, TournamentStatus::Ended);
+        assert!(tournament.agents.iter().all(|agent| agent.byes <= 1));
+        // Every pairing was played on both seeds and never again
+        for agent in &tournament.agents {
+            for opponent in &tournament.agents {
+                let games = tournament.matches.iter().filter(|tournament_match| tournament_match.agents.contains(&agent.name) && tournament_match.agents.contains(&opponent.name)).count();
+                assert!(agent.name == opponent.name || games == 0 || games == 2);
+            }
+        }
+        assert_eq!(tournament.get_standings()[0].name, "a");
+    }
+
+    #[test]
+    fn test_swiss_pairing_of_many_agents_in_their_last_round() {
+        // Agents 0 to 31 played the first 30 rounds of a round robin, only one opponent is left for each of them
+        let agents: Vec<String> = (0..32).map(|agent| agent.to_string()).collect();
+        let get_round_robin_opponent = |agent: usize, round: usize| match agent {
+            31 => round,
+            _ if (2 * round + 31 - agent) % 31 == agent => 31,
+            _ => (2 * round + 31 - agent) % 31,
+        };
+        let have_played = |agent: &str, opponent: &str| {
+            let (agent, opponent): (usize, usize) = (agent.parse().unwrap(), opponent.parse().unwrap());
+            (0..30).any(|round| get_round_robin_opponent(agent, round) == opponent)
+        };
+        let ranked_agents: Vec<&str> = agents.iter().map(String::as_str).collect();
+
+        let (pairings, bye) = TournamentFormat::Swiss { rounds: 31 }.get_pairings(&ranked_agents, have_played, |_| false);
+        assert_eq!(bye, None);
+        let mut paired_agents: Vec<&str> = pairings.iter().flat_map(|(agent, opponent)| [agent.as_str(), opponent.as_str()]).collect();
+        paired_agents.sort_by_key(|agent| agent.parse::<usize>().unwrap());
+        assert_eq!(paired_agents, ranked_agents);
+        assert!(pairings.iter().all(|(agent, opponent)| !have_played(agent, opponent)));
+
+        // After a full round robin every pairing is a rematch, which is played then
+        let (pairings, _) = TournamentFormat::Swiss { rounds: 32 }.get_pairings(&ranked_agents, |_, _| true, |_| false);
+        assert_eq!(pairings.len(), 16);
+    }
+
+    #[test]
+    fn test_external_agents_need_a_timeout_turn_gate() {
+        let game = serde_json::from_value(serde_json::json!({ "max_rounds": 10, "max_players": 2, "map_size": 10 })).unwrap();
+        let mut tournament = Tournament::new(TournamentFormat::RoundRobin, vec![1], game).unwrap();
+        tournament.add_agent("a", Some(BotStrategy::Random)).unwrap();
+        tournament.add_agent("b", None).unwrap();
+        assert!(matches!(tournament.start_next_round(), Err(TournamentError::NoTimeoutForExternalAgents)));
+
+        tournament.agents[1].bot = Some(BotStrategy::Random);
+        tournament.start_next_round().unwrap();
+        assert_eq!(tournament.status, TournamentStatus::Running);
+    }
+
+    #[test]
+    fn test_ratings_follow_the_results() {
+        let mut tournament = create_tournament(TournamentFormat::RoundRobin, &["a", "b"]);
+        tournament.start_next_round().unwrap();
+        tournament.record_result(0, &get_results([("a", 500), ("b", 500)]));
+        assert_eq!(tournament.matches[0].result, Some(MatchResult::Draw));
+        assert_eq!(tournament.get_agent("a").unwrap().rating, INITIAL_RATING);
+
+        tournament.record_result(1, &get_results([("a", 900), ("b", 500)]));
+        let (winner, loser) = (tournament.get_agent("a").unwrap(), tournament.get_agent("b").unwrap());
+        assert_eq!(winner.rating, INITIAL_RATING + RATING_FACTOR / 2.0);
+        assert_eq!(loser.rating, INITIAL_RATING - RATING_FACTOR / 2.0);
+        assert_eq!((winner.wins, winner.draws, loser.losses, loser.points), (1, 1, 1, 0.5));
+    }
+}
@@ -0,0 +1,18 @@
+use thiserror::Error;
+
+/// Reasons why a tournament can't be set up or started.
+#[derive(Error, Debug)]
+pub enum TournamentError {
+    #[error("A tournament needs at least one seed to play its games on")]
+    NoSeeds,
+    #[error("A swiss tournament needs at least one round")]
+    NoRounds,
+    #[error("Tournament has already started")]
+    AlreadyStarted,
+    #[error("Agent {0} is already registered")]
+    AgentAlreadyRegistered(String),
+    #[error("A tournament needs at least two agents, but {0} are registered")]
+    NotEnoughAgents(usize),
+    #[error("Games with external agents need a Timeout turn gate, so that they end even if an agent stops playing")]
+    NoTimeoutForExternalAgents,
+}
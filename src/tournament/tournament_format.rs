@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+/// How the agents of a tournament are paired. Every pairing plays one game on each seed of the tournament.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum TournamentFormat {
+    /// Every agent plays every other agent, all in a single round.
+    #[default]
+    RoundRobin,
+    /// Agents with similar points play each other for the given number of rounds, rematches are avoided where possible.
+    /// With an odd number of agents, one agent sits out every round and gets a win for it.
+    Swiss { rounds: u16 },
+}
+
+impl TournamentFormat {
+    pub fn get_rounds(&self) -> u16 {
+        match self {
+            TournamentFormat::RoundRobin => 1,
+            TournamentFormat::Swiss { rounds } => *rounds,
+        }
+    }
+
+    /// Pairs the agents for the next round. `ranked_agents` are ordered from the best to the worst agent, `have_played`
+    /// tells whether two agents met before and `had_bye` whether an agent already sat out a round.
+    /// Returns the pairings and the agent which sits out this round.
+    pub fn get_pairings(&self, ranked_agents: &[&str], have_played: impl Fn(&str, &str) -> bool, had_bye: impl Fn(&str) -> bool) -> (Vec<(String, String)>, Option<String>) {
+        let mut unpaired: Vec<&str> = ranked_agents.to_vec();
+        match self {
+            TournamentFormat::RoundRobin => {
+                let pairings = unpaired.iter().enumerate()
+                    .flat_map(|(index, agent)| unpaired[index + 1..].iter().map(|opponent| (agent.to_string(), opponent.to_string())))
+                    .collect();
+                (pairings, None)
+            }
+            TournamentFormat::Swiss { .. } => {
+                // The worst ranked agent which didn't sit out a round yet gets the bye
+                let bye = if unpaired.len() % 2 == 1 {
+                    let bye_index = unpaired.iter().rposition(|agent| !had_bye(agent)).unwrap_or(unpaired.len() - 1);
+                    Some(unpaired.remove(bye_index).to_string())
+                } else {
+                    None
+                };
+                let pairings = get_pairings_avoiding_rematches(&unpaired, &have_played);
+                (pairings, bye)
+            }
+        }
+    }
+}
+
+/// Pairs every agent with the best ranked opponent it hasn't played yet. Pairings which are left with a rematch are
+/// repaired by swapping opponents with another pairing once, if that makes both pairings new. Rematches which remain
+/// after that are played, so pairing takes quadratic time instead of searching through every way of pairing the agents.
+fn get_pairings_avoiding_rematches(unpaired: &[&str], have_played: &impl Fn(&str, &str) -> bool) -> Vec<(String, String)> {
+    let mut remaining = unpaired.to_vec();
+    let mut pairings: Vec<(&str, &str)> = Vec::new();
+    while !remaining.is_empty() {
+        let agent = remaining.remove(0);
+        let opponent_index = remaining.iter().position(|opponent| !have_played(agent, opponent)).unwrap_or(0);
+        pairings.push((agent, remaining.remove(opponent_index)));
+    }
+
+    for index in 0..pairings.len() {
+        let (agent, opponent) = pairings[index];
+        if !have_played(agent, opponent) {
+            continue;
+        }
+        let swap = (0..pairings.len()).filter(|&other_index| other_index != index).find_map(|other_index| {
+            let (other_agent, other_opponent) = pairings[other_index];
+            [((agent, other_agent), (opponent, other_opponent)), ((agent, other_opponent), (opponent, other_agent))].into_iter()
+                .find(|(first, second)| !have_played(first.0, first.1) && !have_played(second.0, second.1))
+                .map(|swapped| (other_index, swapped))
+        });
+        if let Some((other_index, (first, second))) = swap {
+            pairings[index] = first;
+            pairings[other_index] = second;
+        }
+    }
+    pairings.into_iter().map(|(agent, opponent)| (agent.to_string(), opponent.to_string())).collect()
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+use crate::trading::external::command::Command;
+
+/// A command which was not executed when the round was processed, together with the reason why.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommandRejection {
+    pub command: Command,
+    pub reason: RejectionReason,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RejectionReason {
+    AttackerNotFound,
+    AttackerDead,
+    TargetNotFound,
+    TargetDead,
+    CannotAttackItself,
+    NotEnoughEnergy,
+    TargetOutOfRange,
+    FriendlyFire,
+    RobotNotFound, // The robot is missing in the command or doesn't belong to the player
+    RobotDead,
+    PlanetNotFound,
+}
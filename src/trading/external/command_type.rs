@@ -8,4 +8,9 @@ pub enum CommandType {
     REGENERATE,
     BUYING,
     SELLING,
+    PICKUP,
+    /// Moves the robot towards `target_id` one planet per round, see `StandingOrder::MoveTo`.
+    MOVE_TO,
+    /// Marks that the player is done with the current round, it is never queued.
+    END_TURN,
 }
@@ -1,10 +1,14 @@
-use rayon::prelude::IntoParallelRefMutIterator;
+use std::collections::HashMap;
+
 use tracing::error;
-use tracing::log::{debug, info};
+use tracing::log::debug;
 use uuid::Uuid;
 
+use crate::game::game_rules::{AttackRange, FriendlyFire};
 use crate::game::game_state::GameState;
-use crate::robot::robot::Robot;
+use crate::trading::external::command::Command;
+use crate::trading::external::command_rejection::{CommandRejection, RejectionReason};
+use crate::trading::external::handler::pickup_command_handler::drop_loot;
 use crate::trading::external::command_type::CommandType;
 
 pub struct DamageReport {
@@ -12,96 +16,175 @@ pub struct DamageReport {
     attacker_name: String,
     defender_id: Uuid,
     damage_to_take: u32,
+    energy_cost: u32,
+    friendly_fire: bool,
 }
 
+/*
+Battles are resolved simultaneously:
+1. Every attack is validated against the state at the start of the battle phase. Robots which die in this round still get to shoot.
+   The target has to be in range (see AttackRange) and may only be an own or a teammate's robot if FriendlyFire allows it.
+   Attacks which fail validation are reported back in the player's rejected_commands.
+2. Energy is only spent for attacks which passed validation.
+3. All damage is applied at once. A robot which dies is killed exactly once: the robot which dealt the most damage to it gets the kill,
+   every other robot which damaged it gets an assist. With FriendlyFire::OnWithoutKillCredit, damage to own and allied robots earns neither.
+4. If the rules say so, dead robots drop their inventory on their planet and the killer's player is paid a bounty.
+ */
 pub async fn calculate_damage_for_round(game_state: &mut GameState) -> Vec<DamageReport> {
+    let player_order = game_state.get_player_order_for_current_round();
     let round_state = game_state.round_states.get_mut(&game_state.current_round).unwrap();
 
-    round_state.player_name_player_map.iter_mut().flat_map(|(_, player)| {
-        player.commands.get_mut(&CommandType::BATTLE).into_iter().flat_map(|commands| {
-            commands.drain(..).filter_map(|command| {
-                let attacker_id = command.command_object.robot_id.expect("Attacker id is required");
-                let target_id = command.command_object.target_id.expect("Target id is required");
-                if attacker_id == target_id {
-                    error!("Robot {} with id {} cannot attack itself", &player.player_name, &attacker_id);
-                    return None;
-                }
+    let battle_commands: Vec<Command> = player_order.iter().flat_map(|player_name| {
+        round_state.player_name_player_map.get_mut(player_name)
+            .and_then(|player| player.commands.get_mut(&CommandType::BATTLE))
+            .map(|commands| commands.drain(..).collect::<Vec<Command>>())
+            .unwrap_or_default()
+    }).collect();
+
+    let mut energy_spent: HashMap<Uuid, u32> = HashMap::new();
+    let mut rejections: Vec<CommandRejection> = Vec::new();
+    let mut damage_reports: Vec<DamageReport> = Vec::new();
+    for command in battle_commands {
+        let attacker_id = command.command_object.robot_id.expect("Attacker id is required");
+        let target_id = command.command_object.target_id.expect("Target id is required");
+        let validated_attack = validate_attack(game_state, &mut energy_spent, &command.player_name, attacker_id, target_id);
+        match validated_attack {
+            Ok((damage_to_take, energy_cost, friendly_fire)) => damage_reports.push(DamageReport {
+                attacker_id,
+                attacker_name: command.player_name,
+                defender_id: target_id,
+                damage_to_take,
+                energy_cost,
+                friendly_fire,
+            }),
+            Err(reason) => {
+                error!("Robot {} of {} cannot attack robot {}: {:?}", &attacker_id, &command.player_name, &target_id, reason);
+                rejections.push(CommandRejection { command, reason });
+            }
+        }
+    }
 
-                player.robots.get(&attacker_id).and_then(|attacker_robot| {
-                    if attacker_robot.is_alive() {
-                        let energy_needed_for_attack = attacker_robot.levels.damage_level.get_int_value() + 1;
-                        if attacker_robot.energy < energy_needed_for_attack {
-                            error!("Robot {} with id {} does not have enough energy to attack", &player.player_name, &attacker_id);
-                            return None;
-                        }
-                        let attacker_damage = attacker_robot.levels.get_damage_for_level();
-                        Some(DamageReport {
-                            attacker_id,
-                            attacker_name: command.player_name,
-                            defender_id: target_id,
-                            damage_to_take: attacker_damage,
-                        })
-                    } else {
-                        error!("Attacker robot of {} with id {} is dead; it cannot attack", &player.player_name, &attacker_id);
-                        None
-                    }
-                })
-            })
-        })
-    }).collect()
+    for rejection in rejections {
+        if let Some(player) = game_state.get_player_for_current_round_as_mut(&rejection.command.player_name) {
+            player.rejected_commands.push(rejection);
+        }
+    }
+    damage_reports
+}
+
+/// Checks an attack against the state at the start of the battle phase.
+/// Returns the damage it deals, the energy it costs and whether it hits a robot of the attacking player or one of their teammates.
+fn validate_attack(game_state: &GameState, energy_spent: &mut HashMap<Uuid, u32>, player_name: &str, attacker_id: Uuid, target_id: Uuid) -> Result<(u32, u32, bool), RejectionReason> {
+    let round_state = &game_state.round_states[&game_state.current_round];
+    let players = &round_state.player_name_player_map;
+    if attacker_id == target_id {
+        return Err(RejectionReason::CannotAttackItself);
+    }
+    let attacker_robot = players.get(player_name).and_then(|player| player.robots.get(&attacker_id))
+        .ok_or(RejectionReason::AttackerNotFound)?;
+    if !attacker_robot.is_alive() {
+        return Err(RejectionReason::AttackerDead);
+    }
+    let target_robot = players.values().find_map(|player| player.robots.get(&target_id))
+        .ok_or(RejectionReason::TargetNotFound)?;
+    if !target_robot.is_alive() {
+        return Err(RejectionReason::TargetDead);
+    }
+    let friendly_fire = game_state.get_player_name_by_robot_id(&target_id).is_some_and(|target_player_name| game_state.are_allies(target_player_name, player_name));
+    if friendly_fire && game_state.rules.friendly_fire == FriendlyFire::Off {
+        return Err(RejectionReason::FriendlyFire);
+    }
+    let is_in_range = attacker_robot.planet_id == target_robot.planet_id || match game_state.rules.attack_range {
+        AttackRange::SamePlanet => false,
+        AttackRange::Adjacent => round_state.map.get_planet(&attacker_robot.planet_id)
+            .is_some_and(|planet| planet.neighbours.values().any(|neighbour_id| *neighbour_id == target_robot.planet_id)),
+    };
+    if !is_in_range {
+        return Err(RejectionReason::TargetOutOfRange);
+    }
+    let energy_cost = attacker_robot.levels.damage_level.get_int_value() + 1;
+    let already_spent = energy_spent.entry(attacker_id).or_insert(0);
+    if attacker_robot.energy < *already_spent + energy_cost {
+        return Err(RejectionReason::NotEnoughEnergy);
+    }
+    *already_spent += energy_cost;
+    Ok((attacker_robot.levels.get_damage_for_level(), energy_cost, friendly_fire))
 }
 
 pub fn apply_damage_for_round(damage_reports: Vec<DamageReport>, game_state: &mut GameState) {
-    pub struct KillReport {
-        pub attacker_robot_id: Uuid,
-        pub attacker_name: String,
-        pub player_name_whose_robot_got_killed: String,
-        pub killed_robot: Robot,
-    }
-    let mut kill_reports = Vec::new();
-    for damage_report in damage_reports {
-        if let Some(attackers_robots) = game_state.get_robots_for_current_round(&damage_report.attacker_name) {
-            if let Some(attacker_robot) = attackers_robots.get_mut(&damage_report.attacker_id) {
-                let energy_cost_for_attack = attacker_robot.levels.damage_level.get_int_value() + 1;
-                attacker_robot.energy -= energy_cost_for_attack; //TODO: Eigentlich muss das weiter unten hin, nach dem check
-
-                if let Some((target_robot, target_player_name)) = game_state.get_robot_and_playername_for_current_round_by_robot_id(&damage_report.defender_id) {
-                    if damage_report.attacker_id.clone() == target_robot.robot_id.clone() {
-                        error!("Robot of {} with id {} cannot attack itself", damage_report.attacker_name, damage_report.attacker_id);
-                        continue;
-                    }
-                    target_robot.take_damage(damage_report.damage_to_take);
-                    if !target_robot.is_alive() {
-                        debug!("Robot {} of player {} was killed by {} {}", damage_report.defender_id, target_player_name, damage_report.attacker_name, damage_report.attacker_id);
-                        let kill_report = KillReport {
-                            attacker_name: damage_report.attacker_name.clone(),
-                            attacker_robot_id: damage_report.attacker_id,
-                            player_name_whose_robot_got_killed: target_player_name.clone(),
-                            killed_robot: target_robot.clone(), // Consider avoiding clone
-                        };
-                        if kill_report.attacker_robot_id == kill_report.killed_robot.robot_id {
-                            error!("Robot of {} with id {} cannot kill itself", kill_report.attacker_name, kill_report.attacker_robot_id);
-                        } else {
-                            kill_reports.push(kill_report);
-                        }
-                    }
-                } else {
-                    error!("Target robot not found for ID {}", damage_report.defender_id);
-                }
-            } else {
-                error!("Attacker robot not found for ID {}", damage_report.attacker_id);
-            }
-        } else {
-            error!("Attacker robot not found for ID {}", damage_report.attacker_id);
+    let friendly_fire = game_state.rules.friendly_fire.clone();
+    let should_drop_loot = game_state.rules.drop_loot;
+    let kill_bounty = game_state.rules.kill_bounty;
+    // Pay for the attacks
+    for damage_report in &damage_reports {
+        match game_state.get_robot_for_current_round_by_player_id_and_robot_id(&damage_report.attacker_name, &damage_report.attacker_id) {
+            Some(attacker_robot) => attacker_robot.energy = attacker_robot.energy.saturating_sub(damage_report.energy_cost),
+            None => error!("Attacker robot not found for ID {}", damage_report.attacker_id),
         }
     }
 
-    for report in kill_reports {
-        if let Some(player_state) = game_state.get_player_for_current_round_as_mut(&report.attacker_name) {
-            let mut existing_killed_robots = player_state.killed_robots.entry(report.attacker_robot_id).or_insert_with(Vec::new);
-            existing_killed_robots.push((report.player_name_whose_robot_got_killed, report.killed_robot));
-        } else {
-            error!("Player {} (Attacker) not found", report.attacker_name);
+    // Sum up the damage per defender, keeping the order in which the attacks were resolved
+    let mut defender_ids: Vec<Uuid> = Vec::new();
+    let mut damage_by_defender: HashMap<Uuid, Vec<&DamageReport>> = HashMap::new();
+    for damage_report in &damage_reports {
+        damage_by_defender.entry(damage_report.defender_id).or_insert_with(|| {
+            defender_ids.push(damage_report.defender_id);
+            Vec::new()
+        }).push(damage_report);
+    }
+
+    for defender_id in defender_ids {
+        let reports = &damage_by_defender[&defender_id];
+        let total_damage: u32 = reports.iter().map(|report| report.damage_to_take).sum();
+        let Some((target_robot, target_player_name)) = game_state.get_robot_and_playername_for_current_round_by_robot_id(&defender_id) else {
+            error!("Target robot not found for ID {}", defender_id);
+            continue;
+        };
+        target_robot.take_damage(total_damage);
+        if target_robot.is_alive() {
+            continue;
+        }
+        let killed_robot = target_robot.clone();
+        if should_drop_loot {
+            let inventory = std::mem::take(&mut target_robot.inventory);
+            let planet = game_state.round_states.get_mut(&game_state.current_round).unwrap()
+                .map.get_planet_as_mut(&killed_robot.planet_id).expect("Planet of killed robot not found");
+            debug!("Robot {} dropped {:?} on planet {}", defender_id, inventory, planet.planet_id);
+            drop_loot(inventory, &mut planet.loot);
+        }
+
+        // Damage dealt per attacking robot. On a tie, the robot which was resolved first gets the kill.
+        let mut damage_by_attacker: Vec<(Uuid, String, u32, bool)> = Vec::new();
+        for report in reports.iter().filter(|report| !report.friendly_fire || friendly_fire == FriendlyFire::On) {
+            match damage_by_attacker.iter_mut().find(|(attacker_id, _, _, _)| *attacker_id == report.attacker_id) {
+                Some((_, _, damage, _)) => *damage += report.damage_to_take,
+                None => damage_by_attacker.push((report.attacker_id, report.attacker_name.clone(), report.damage_to_take, report.friendly_fire)),
+            }
+        }
+        if damage_by_attacker.is_empty() {
+            debug!("Robot {} of player {} was killed by friendly fire", defender_id, target_player_name);
+            continue;
+        }
+        let killer_index = damage_by_attacker.iter().enumerate()
+            .fold(0, |best, (index, (_, _, damage, _))| if *damage > damage_by_attacker[best].2 { index } else { best });
+
+        for (index, (attacker_id, attacker_name, _, is_friendly_fire)) in damage_by_attacker.into_iter().enumerate() {
+            let Some(player_state) = game_state.get_player_for_current_round_as_mut(&attacker_name) else {
+                error!("Player {} (Attacker) not found", attacker_name);
+                continue;
+            };
+            if index == killer_index {
+                debug!("Robot {} of player {} was killed by {} {}", defender_id, target_player_name, attacker_name, attacker_id);
+                player_state.killed_robots.entry(attacker_id).or_default().push((target_player_name.clone(), killed_robot.clone()));
+                // Killing an own or allied robot must not be a way to make money
+                if !is_friendly_fire {
+                    player_state.money.amount += kill_bounty;
+                    player_state.total_money_made.amount += kill_bounty;
+                }
+            } else {
+                debug!("Robot {} of player {} assisted in killing robot {}", attacker_id, attacker_name, defender_id);
+                player_state.assisted_kills.entry(attacker_id).or_default().push((target_player_name.clone(), killed_robot.clone()));
+            }
         }
     }
 }
@@ -112,7 +195,7 @@ pub fn delete_commands_for_dead_robots(game_state: &mut GameState) {
 
     for player in player_states.values_mut() {
         let players_robots = &mut player.robots;
-        //clear mining and regenerating commands for dead robots, in case they had such commands
+        //clear mining, regenerating and pickup commands for dead robots, in case they had such commands
         for (robot_id, robot) in players_robots.iter_mut() {
             if !robot.is_alive() {
                 if let Some(mining_commands) = player.commands.get_mut(&CommandType::MINING) {
@@ -121,7 +204,157 @@ pub fn delete_commands_for_dead_robots(game_state: &mut GameState) {
                 if let Some(regenerating_commands) = player.commands.get_mut(&CommandType::REGENERATE) {
                     regenerating_commands.retain(|command| command.command_object.robot_id.unwrap() != *robot_id);
                 }
+                if let Some(pickup_commands) = player.commands.get_mut(&CommandType::PICKUP) {
+                    pickup_commands.retain(|command| command.command_object.robot_id.unwrap() != *robot_id);
+                }
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::game_rules::GameRules;
+    use crate::planet::direction::Direction;
+    use crate::planet::planet::Planet;
+    use crate::robot::robot::Robot;
+    use crate::test_support::{create_game_state, STARTING_MONEY};
+    use crate::trading::external::command_object::CommandObject;
+
+    use super::*;
+
+    fn attack(game_state: &mut GameState, player_name: &str, attacker_id: Uuid, target_id: Uuid) {
+        let command = Command {
+            player_name: player_name.to_string(),
+            game_id: game_state.game_id.to_string(),
+            command_type: CommandType::BATTLE,
+            command_object: CommandObject { robot_id: Some(attacker_id), planet_id: None, target_id: Some(target_id), item_name: None, item_quantity: None },
+        };
+        game_state.get_player_for_current_round_as_mut(player_name).unwrap().commands.entry(CommandType::BATTLE).or_default().push_back(command);
+    }
+
+    #[tokio::test]
+    async fn test_kill_is_recorded_once_with_assists() {
+        let planet = Planet::new(Uuid::new_v4(), 1);
+        let first_attacker = Robot::new(Uuid::new_v4(), planet.planet_id);
+        let second_attacker = Robot::new(Uuid::new_v4(), planet.planet_id);
+        let mut victim = Robot::new(Uuid::new_v4(), planet.planet_id);
+        victim.health = 2;
+        let (first_attacker_id, second_attacker_id, victim_id) = (first_attacker.robot_id, second_attacker.robot_id, victim.robot_id);
+        let mut game_state = create_game_state(vec![("alice", vec![first_attacker, second_attacker]), ("bob", vec![victim])], vec![planet], GameRules::default());
+        attack(&mut game_state, "alice", first_attacker_id, victim_id);
+        attack(&mut game_state, "alice", second_attacker_id, victim_id);
+
+        let damage_reports = calculate_damage_for_round(&mut game_state).await;
+        apply_damage_for_round(damage_reports, &mut game_state);
+
+        let alice = game_state.get_player_for_round("alice", 0).unwrap();
+        let kills: usize = alice.killed_robots.values().map(|kills| kills.len()).sum();
+        let assists: usize = alice.assisted_kills.values().map(|assists| assists.len()).sum();
+        assert_eq!(kills, 1);
+        assert_eq!(assists, 1);
+        assert!(alice.robots.values().all(|robot| robot.energy == robot.levels.get_energy_for_level() - 1));
+        assert!(!game_state.get_player_for_round("bob", 0).unwrap().robots[&victim_id].is_alive());
+    }
+
+    #[tokio::test]
+    async fn test_damage_is_simultaneous_and_energy_only_spent_for_valid_attacks() {
+        let planet = Planet::new(Uuid::new_v4(), 1);
+        let mut alice_robot = Robot::new(Uuid::new_v4(), planet.planet_id);
+        alice_robot.health = 1;
+        alice_robot.energy = 1;
+        let mut bob_robot = Robot::new(Uuid::new_v4(), planet.planet_id);
+        bob_robot.health = 1;
+        let (alice_robot_id, bob_robot_id) = (alice_robot.robot_id, bob_robot.robot_id);
+        let mut game_state = create_game_state(vec![("alice", vec![alice_robot]), ("bob", vec![bob_robot])], vec![planet], GameRules::default());
+        attack(&mut game_state, "alice", alice_robot_id, bob_robot_id);
+        // Alice's robot only has energy for one attack
+        attack(&mut game_state, "alice", alice_robot_id, bob_robot_id);
+        attack(&mut game_state, "bob", bob_robot_id, alice_robot_id);
+
+        let damage_reports = calculate_damage_for_round(&mut game_state).await;
+        assert_eq!(damage_reports.len(), 2);
+        apply_damage_for_round(damage_reports, &mut game_state);
+
+        let alice = game_state.get_player_for_round("alice", 0).unwrap();
+        let bob = game_state.get_player_for_round("bob", 0).unwrap();
+        assert_eq!(alice.robots[&alice_robot_id].energy, 0);
+        assert!(!alice.robots[&alice_robot_id].is_alive());
+        assert!(!bob.robots[&bob_robot_id].is_alive());
+        assert_eq!(alice.killed_robots[&alice_robot_id].len(), 1);
+        assert_eq!(bob.killed_robots[&bob_robot_id].len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_attack_range() {
+        let mut planet = Planet::new(Uuid::new_v4(), 1);
+        let neighbour = Planet::new(Uuid::new_v4(), 1);
+        planet.set_neighbour(Direction::SOUTH, neighbour.planet_id);
+        let alice_robot = Robot::new(Uuid::new_v4(), planet.planet_id);
+        let bob_robot = Robot::new(Uuid::new_v4(), neighbour.planet_id);
+        let (alice_robot_id, bob_robot_id) = (alice_robot.robot_id, bob_robot.robot_id);
+        let players = vec![("alice", vec![alice_robot]), ("bob", vec![bob_robot])];
+
+        let mut game_state = create_game_state(players.clone(), vec![planet.clone(), neighbour.clone()], GameRules::default());
+        attack(&mut game_state, "alice", alice_robot_id, bob_robot_id);
+        assert!(calculate_damage_for_round(&mut game_state).await.is_empty());
+        let alice = game_state.get_player_for_round("alice", 0).unwrap();
+        assert_eq!(alice.rejected_commands.len(), 1);
+        assert_eq!(alice.rejected_commands[0].reason, RejectionReason::TargetOutOfRange);
+
+        let rules = GameRules { attack_range: AttackRange::Adjacent, ..GameRules::default() };
+        let mut game_state = create_game_state(players, vec![planet, neighbour], rules);
+        attack(&mut game_state, "alice", alice_robot_id, bob_robot_id);
+        assert_eq!(calculate_damage_for_round(&mut game_state).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_friendly_fire() {
+        let planet = Planet::new(Uuid::new_v4(), 1);
+        let attacker = Robot::new(Uuid::new_v4(), planet.planet_id);
+        let mut own_robot = Robot::new(Uuid::new_v4(), planet.planet_id);
+        own_robot.health = 1;
+        let (attacker_id, own_robot_id) = (attacker.robot_id, own_robot.robot_id);
+        let players = vec![("alice", vec![attacker, own_robot])];
+
+        let mut game_state = create_game_state(players.clone(), vec![planet.clone()], GameRules::default());
+        attack(&mut game_state, "alice", attacker_id, own_robot_id);
+        assert!(calculate_damage_for_round(&mut game_state).await.is_empty());
+        assert_eq!(game_state.get_player_for_round("alice", 0).unwrap().rejected_commands[0].reason, RejectionReason::FriendlyFire);
+
+        let rules = GameRules { friendly_fire: FriendlyFire::OnWithoutKillCredit, ..GameRules::default() };
+        let mut game_state = create_game_state(players, vec![planet], rules);
+        attack(&mut game_state, "alice", attacker_id, own_robot_id);
+        let damage_reports = calculate_damage_for_round(&mut game_state).await;
+        apply_damage_for_round(damage_reports, &mut game_state);
+        let alice = game_state.get_player_for_round("alice", 0).unwrap();
+        assert!(!alice.robots[&own_robot_id].is_alive());
+        assert!(alice.killed_robots.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_kill_bounty_is_only_paid_for_enemy_robots() {
+        let planet = Planet::new(Uuid::new_v4(), 1);
+        let attacker = Robot::new(Uuid::new_v4(), planet.planet_id);
+        let mut own_robot = Robot::new(Uuid::new_v4(), planet.planet_id);
+        own_robot.health = 1;
+        let mut enemy_robot = Robot::new(Uuid::new_v4(), planet.planet_id);
+        enemy_robot.health = 1;
+        let (attacker_id, own_robot_id, enemy_robot_id) = (attacker.robot_id, own_robot.robot_id, enemy_robot.robot_id);
+        let rules = GameRules { friendly_fire: FriendlyFire::On, kill_bounty: 100, ..GameRules::default() };
+        let mut game_state = create_game_state(vec![("alice", vec![attacker, own_robot]), ("bob", vec![enemy_robot])], vec![planet], rules);
+
+        attack(&mut game_state, "alice", attacker_id, own_robot_id);
+        let damage_reports = calculate_damage_for_round(&mut game_state).await;
+        apply_damage_for_round(damage_reports, &mut game_state);
+        let alice = game_state.get_player_for_round("alice", 0).unwrap();
+        assert_eq!(alice.killed_robots[&attacker_id].len(), 1);
+        assert_eq!((alice.money.amount, alice.total_money_made.amount), (STARTING_MONEY, STARTING_MONEY));
+
+        attack(&mut game_state, "alice", attacker_id, enemy_robot_id);
+        let damage_reports = calculate_damage_for_round(&mut game_state).await;
+        apply_damage_for_round(damage_reports, &mut game_state);
+        let alice = game_state.get_player_for_round("alice", 0).unwrap();
+        assert_eq!((alice.money.amount, alice.total_money_made.amount), (STARTING_MONEY + 100, STARTING_MONEY + 100));
+    }
+}
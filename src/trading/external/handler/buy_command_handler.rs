@@ -10,10 +10,11 @@ use crate::robot::robot_stats::RobotStats;
 use crate::trading::external::command_type::CommandType;
 
 pub fn handle_buy_commands(game_state: &mut GameState, spawn_robots: bool) {
+    let player_order = game_state.get_player_order_for_current_round();
     let round_state = game_state.round_states.get_mut(&game_state.current_round).unwrap();
     let map = &round_state.map;
-    let mut player_name_player_map = round_state.player_name_player_map.values_mut();
-    for player in player_name_player_map {
+    for player_name in &player_order {
+        let player = round_state.player_name_player_map.get_mut(player_name).expect("Player not found");
         let money = &mut player.money;
         if let Some(buy_commands) = player.commands.get_mut(&CommandType::BUYING) {
             if buy_commands.is_empty() {
@@ -107,16 +108,22 @@ pub fn handle_buy_commands(game_state: &mut GameState, spawn_robots: bool) {
                                     //debug!("Skipping to spawn robots, because we are 'hypothetically' transforming the state");
                                     continue
                                 }
-                                //choose random planet which is not empty on map and spawn robot there
+                                //spawn robot on the player's home planet, or choose random spawn point or random planet which is not empty on map
                                 for _ in 0..amount {
-                                    let rand = rand::random::<usize>() % map.indices.len();
-                                    let planet_id = map.indices.keys().nth(rand).expect("Planet Index not found, probably out of bounds");
+                                    let planet_id = match player.home_planet {
+                                        Some(home_planet) => home_planet,
+                                        None if map.spawn_points.is_empty() => {
+                                            let rand = rand::random::<usize>() % map.indices.len();
+                                            *map.indices.keys().nth(rand).expect("Planet Index not found, probably out of bounds")
+                                        }
+                                        None => map.spawn_points[rand::random::<usize>() % map.spawn_points.len()],
+                                    };
                                     let robot = Robot::new(
                                         Uuid::new_v4(),
-                                        *planet_id,
+                                        planet_id,
                                     );
                                     info!("Player {} bought robot {} and spawned it on planet {}", player.player_name, robot.robot_id, planet_id);
-                                    player.visited_planets.insert(*planet_id);
+                                    player.visited_planets.insert(planet_id);
                                     player.robots.insert(robot.robot_id, robot);
                                 }
                             }
@@ -1,8 +1,11 @@
 use tracing::log::info;
+use uuid::Uuid;
 
+use crate::game::game_rules::ContestedMining;
 use crate::game::game_state::GameState;
 use crate::robot::robot_levels::RobotLevels;
 use crate::trading::external::command_type::CommandType;
+use crate::trading::external::handler::pickup_command_handler::pick_up_loot;
 
 // pub fn handle_mining_command(mining_command: Command, mut game_state: GameState) -> Option<GameState>{
 //     let robot_id = mining_command.command_object.robot_id.expect("Robot id was missing in mining command");
@@ -50,44 +53,143 @@ use crate::trading::external::command_type::CommandType;
 //     Some(game_state)
 // }
 
+struct MiningClaim {
+    player_name: String,
+    robot_id: Uuid,
+    planet_id: Uuid,
+    requested_amount: u32,
+}
+
 pub fn handle_mining_commands(game_state: &mut GameState) {
+    let player_order = game_state.get_player_order_for_current_round();
+    let contested_mining = game_state.rules.contested_mining.clone();
     let round_state = game_state.round_states.get_mut(&game_state.current_round).unwrap();
     let map = &mut round_state.map;
-    let player_name_player_map = round_state.player_name_player_map.values_mut();
-    for player in player_name_player_map {
+
+    // Collect what every robot wants to mine, before anything is taken from the planets.
+    // Loot lying on a planet is picked up first, in player order, and does not depend on the mining level.
+    let mut claims: Vec<MiningClaim> = Vec::new();
+    for player_name in &player_order {
+        let player = round_state.player_name_player_map.get_mut(player_name).expect("Player not found");
         if let Some(mining_commands_queue) = player.commands.get_mut(&CommandType::MINING) {
             while let Some(mining_command) = mining_commands_queue.pop_front() {
                 let robot_id = mining_command.command_object.robot_id.expect("Robot id is required");
                 let target_planet_id = mining_command.command_object.target_id.expect("Target id is required for mining commands");
                 let robot = player.robots.get_mut(&robot_id).expect("Robot not found");
                 let minable_resources_of_robot = RobotLevels::get_mineable_resoures(&robot.levels);
-                let mut target_planet = map.get_planet_as_mut(&target_planet_id).expect("Target planet not found");
-                if robot.planet_id == target_planet_id {
-                    if robot.is_storage_full() {
-                        info!("Robot {} has a full inventory and cannot mine", robot_id);
-                    } else {
-                        let mining_amount_for_level = robot.levels.get_mining_speed_for_level();
-                        let potential_mining_amount = std::cmp::min(robot.get_free_storage_space(), mining_amount_for_level);
-                        if let Some((resource, resource_amount)) = &mut target_planet.resources {
-                            if !minable_resources_of_robot.contains(resource) {
-                                info!("Robot {} cannot mine {:?} on planet {} mining level is {:?}", robot_id, resource, target_planet_id, robot.levels.mining_level);
-                                continue;
-                            }
-                            if *resource_amount == 0 {
-                                info!("No resources left to mine on planet {}", target_planet_id);
-                            } else {
-                                let mining_amount = std::cmp::min(potential_mining_amount, *resource_amount);
-                                robot.add_resource_to_inventory(resource, &mining_amount);
-                                *resource_amount -= mining_amount;
-                                info!("Robot {} mined {} {:?} on planet {}", robot_id, mining_amount, resource, target_planet_id);
-                                if *resource_amount == 0 {
-                                    target_planet.resources = None;
-                                }
-                            }
-                        }
+                let target_planet = map.get_planet_as_mut(&target_planet_id).expect("Target planet not found");
+                if robot.planet_id != target_planet_id {
+                    continue;
+                }
+                if robot.is_storage_full() {
+                    info!("Robot {} has a full inventory and cannot mine", robot_id);
+                    continue;
+                }
+                let mut mining_capacity = std::cmp::min(robot.get_free_storage_space(), robot.levels.get_mining_speed_for_level());
+                if !target_planet.loot.is_empty() {
+                    let picked_up_amount = pick_up_loot(robot, &mut target_planet.loot, mining_capacity);
+                    info!("Robot {} picked up {} resources of loot on planet {}", robot_id, picked_up_amount, target_planet_id);
+                    mining_capacity -= picked_up_amount;
+                    if mining_capacity == 0 {
+                        continue;
+                    }
+                }
+                match &target_planet.resources {
+                    Some((resource, _)) if !minable_resources_of_robot.contains(resource) => {
+                        info!("Robot {} cannot mine {:?} on planet {} mining level is {:?}", robot_id, resource, target_planet_id, robot.levels.mining_level);
+                    }
+                    Some((_, 0)) => info!("No resources left to mine on planet {}", target_planet_id),
+                    Some(_) => claims.push(MiningClaim {
+                        player_name: player_name.clone(),
+                        robot_id,
+                        planet_id: target_planet_id,
+                        requested_amount: mining_capacity,
+                    }),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    // Split the resources of every planet between the robots mining on it.
+    let mut planet_ids: Vec<Uuid> = Vec::new();
+    for claim in &claims {
+        if !planet_ids.contains(&claim.planet_id) {
+            planet_ids.push(claim.planet_id);
+        }
+    }
+    for planet_id in planet_ids {
+        let claims_for_planet: Vec<&MiningClaim> = claims.iter().filter(|claim| claim.planet_id == planet_id).collect();
+        let target_planet = map.get_planet_as_mut(&planet_id).expect("Target planet not found");
+        let Some((resource, resource_amount)) = &mut target_planet.resources else { continue };
+        let requested_amounts: Vec<u32> = claims_for_planet.iter().map(|claim| claim.requested_amount).collect();
+        let mined_amounts = allocate_contested_amounts(&requested_amounts, *resource_amount, &contested_mining);
+        if requested_amounts.iter().sum::<u32>() > *resource_amount {
+            info!("Planet {} is contested by {} robots, splitting {} {:?} {:?}", planet_id, claims_for_planet.len(), resource_amount, resource, contested_mining);
+        }
+        for (claim, mining_amount) in claims_for_planet.iter().zip(mined_amounts) {
+            if mining_amount == 0 {
+                continue;
+            }
+            let robot = round_state.player_name_player_map.get_mut(&claim.player_name)
+                .and_then(|player| player.robots.get_mut(&claim.robot_id))
+                .expect("Robot not found");
+            let mining_amount = std::cmp::min(mining_amount, robot.get_free_storage_space());
+            robot.add_resource_to_inventory(resource, &mining_amount);
+            *resource_amount -= mining_amount;
+            info!("Robot {} mined {} {:?} on planet {}", claim.robot_id, mining_amount, resource, planet_id);
+        }
+        if *resource_amount == 0 {
+            target_planet.resources = None;
+        }
+    }
+}
+
+/// Splits `available` between claims which requested the given amounts. No claim gets more than it requested.
+fn allocate_contested_amounts(requested_amounts: &[u32], available: u32, contested_mining: &ContestedMining) -> Vec<u32> {
+    let total_requested: u64 = requested_amounts.iter().map(|amount| *amount as u64).sum();
+    if total_requested <= available as u64 {
+        return requested_amounts.to_vec();
+    }
+    match contested_mining {
+        ContestedMining::InPlayerOrder => {
+            let mut remaining = available;
+            requested_amounts.iter().map(|requested| {
+                let amount = std::cmp::min(*requested, remaining);
+                remaining -= amount;
+                amount
+            }).collect()
+        }
+        ContestedMining::Proportional => {
+            let mut amounts: Vec<u32> = requested_amounts.iter()
+                .map(|requested| (*requested as u64 * available as u64 / total_requested) as u32)
+                .collect();
+            // Hand out what is left due to rounding down, one unit at a time in player order.
+            let mut remaining = available - amounts.iter().sum::<u32>();
+            while remaining > 0 {
+                for (amount, requested) in amounts.iter_mut().zip(requested_amounts) {
+                    if remaining > 0 && *amount < *requested {
+                        *amount += 1;
+                        remaining -= 1;
                     }
                 }
             }
+            amounts
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate_contested_amounts() {
+        assert_eq!(allocate_contested_amounts(&[5, 5], 20, &ContestedMining::InPlayerOrder), vec![5, 5]);
+        assert_eq!(allocate_contested_amounts(&[5, 5], 20, &ContestedMining::Proportional), vec![5, 5]);
+        assert_eq!(allocate_contested_amounts(&[5, 5], 7, &ContestedMining::InPlayerOrder), vec![5, 2]);
+        assert_eq!(allocate_contested_amounts(&[5, 5], 7, &ContestedMining::Proportional), vec![4, 3]);
+        assert_eq!(allocate_contested_amounts(&[10, 2, 8], 10, &ContestedMining::Proportional), vec![5, 1, 4]);
+        assert_eq!(allocate_contested_amounts(&[2, 2, 2], 1, &ContestedMining::Proportional), vec![1, 0, 0]);
+    }
+}
@@ -6,4 +6,8 @@ pub mod battle_command_handler;
 
 pub mod regenerate_command_handler;
 
-pub mod mining_command_handler;
\ No newline at end of file
+pub mod mining_command_handler;
+
+pub mod pickup_command_handler;
+
+pub mod standing_order_handler;
@@ -4,10 +4,13 @@ use crate::game::game_state::GameState;
 use crate::trading::external::command_type::CommandType;
 
 pub fn handle_movement_commands(game_state: &mut GameState) {
+    let player_order = game_state.get_player_order_for_current_round();
+    let movement_cost_model = game_state.rules.movement_cost.clone();
     let round_state = game_state.round_states.get_mut(&game_state.current_round).unwrap();
     let map = &round_state.map;
 
-    for player in round_state.player_name_player_map.values_mut() {
+    for player_name in &player_order {
+        let player = round_state.player_name_player_map.get_mut(player_name).expect("Player not found");
         if let Some(movement_commands_queue) = player.commands.get_mut(&CommandType::MOVEMENT) {
             while let Some(movement_command) = movement_commands_queue.pop_front() {
                 let robot_id = movement_command.command_object.robot_id.expect("Robot id is required");
@@ -29,13 +32,15 @@ pub fn handle_movement_commands(game_state: &mut GameState) {
                     info!("Robot {} cannot move to planet {} because it is not a neighbour", robot_id, target_planet_id);
                     continue;
                 }
+                let target_planet = map.get_planet(&target_planet_id).expect("Target planet not found");
+                let movement_cost = movement_cost_model.get_movement_cost(current_planet, target_planet);
 
-                if robot.energy < current_planet.movement_difficulty as u32 {
+                if robot.energy < movement_cost {
                     info!("Robot {} cannot move to planet {} because it does not have enough energy", robot_id, target_planet_id);
                     continue;
                 }
 
-                robot.energy -= current_planet.movement_difficulty as u32;
+                robot.energy -= movement_cost;
                 robot.planet_id = target_planet_id;
                 info!("Robot {} moved to planet {}", robot_id, target_planet_id);
 
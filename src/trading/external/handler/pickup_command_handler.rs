@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use tracing::log::{error, info};
+
+use crate::game::game_state::GameState;
+use crate::planet::resource::Resource;
+use crate::robot::robot::Robot;
+use crate::trading::external::command_rejection::{CommandRejection, RejectionReason};
+use crate::trading::external::command_type::CommandType;
+
+pub fn handle_pickup_commands(game_state: &mut GameState) {
+    let player_order = game_state.get_player_order_for_current_round();
+    let round_state = game_state.round_states.get_mut(&game_state.current_round).unwrap();
+    let map = &mut round_state.map;
+
+    for player_name in &player_order {
+        let Some(player) = round_state.player_name_player_map.get_mut(player_name) else {
+            error!("Player {} not found", player_name);
+            continue;
+        };
+        let Some(pickup_commands) = player.commands.get_mut(&CommandType::PICKUP) else { continue };
+        while let Some(pickup_command) = pickup_commands.pop_front() {
+            let Some((robot_id, robot)) = pickup_command.command_object.robot_id.and_then(|robot_id| Some((robot_id, player.robots.get_mut(&robot_id)?))) else {
+                player.rejected_commands.push(CommandRejection { command: pickup_command, reason: RejectionReason::RobotNotFound });
+                continue;
+            };
+            if !robot.is_alive() {
+                info!("Robot {} is dead and cannot pick up loot", robot_id);
+                player.rejected_commands.push(CommandRejection { command: pickup_command, reason: RejectionReason::RobotDead });
+                continue;
+            }
+            let Some(planet) = map.get_planet_as_mut(&robot.planet_id) else {
+                error!("Planet {} of robot {} not found", robot.planet_id, robot_id);
+                player.rejected_commands.push(CommandRejection { command: pickup_command, reason: RejectionReason::PlanetNotFound });
+                continue;
+            };
+            if planet.loot.is_empty() {
+                info!("There is no loot on planet {} for robot {}", planet.planet_id, robot_id);
+                continue;
+            }
+            let free_storage_space = robot.get_free_storage_space();
+            let picked_up_amount = pick_up_loot(robot, &mut planet.loot, free_storage_space);
+            info!("Robot {} picked up {} resources on planet {}", robot_id, picked_up_amount, planet.planet_id);
+        }
+    }
+}
+
+/// Moves up to `max_amount` resources from the loot pile into the robot's inventory, the most valuable resources first.
+/// Returns how much was picked up.
+pub fn pick_up_loot(robot: &mut Robot, loot: &mut HashMap<Resource, u32>, max_amount: u32) -> u32 {
+    let mut picked_up_amount = 0;
+    let mut resources = Resource::variants();
+    resources.sort_by_key(|resource| std::cmp::Reverse(resource.get_selling_value()));
+    for resource in &resources {
+        let Some(amount_in_pile) = loot.get_mut(resource) else { continue };
+        let amount = std::cmp::min(*amount_in_pile, max_amount - picked_up_amount);
+        if amount > 0 {
+            robot.add_resource_to_inventory(resource, &amount);
+            *amount_in_pile -= amount;
+            picked_up_amount += amount;
+        }
+    }
+    loot.retain(|_, amount| *amount > 0);
+    picked_up_amount
+}
+
+/// Adds the inventory of a dead robot to a loot pile.
+pub fn drop_loot(inventory: HashMap<Resource, u32>, loot: &mut HashMap<Resource, u32>) {
+    for (resource, amount) in inventory {
+        *loot.entry(resource).or_insert(0) += amount;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::game::game_rules::GameRules;
+    use crate::planet::planet::Planet;
+    use crate::test_support::{create_game_state, create_robots};
+    use crate::trading::external::command::Command;
+    use crate::trading::external::command_object::CommandObject;
+
+    use super::*;
+
+    fn pick_up(game_state: &mut GameState, player_name: &str, robot_id: Option<Uuid>) {
+        let command = Command {
+            player_name: player_name.to_string(),
+            game_id: game_state.game_id.to_string(),
+            command_type: CommandType::PICKUP,
+            command_object: CommandObject { robot_id, planet_id: None, target_id: None, item_name: None, item_quantity: None },
+        };
+        game_state.get_player_for_current_round_as_mut(player_name).unwrap().commands.entry(CommandType::PICKUP).or_default().push_back(command);
+    }
+
+    #[test]
+    fn test_invalid_pickup_commands_are_rejected() {
+        let mut planet = Planet::new(Uuid::new_v4(), 1);
+        planet.loot = HashMap::from([(Resource::COAL, 10)]);
+        let planet_id = planet.planet_id;
+        let mut game_state = create_game_state(vec![("alice", create_robots(2, planet_id)), ("bob", create_robots(1, planet_id))], vec![planet], GameRules::default());
+        let alice_robot_ids: Vec<Uuid> = game_state.get_player_for_round("alice", 0).unwrap().robots.keys().copied().collect();
+        let bob_robot_id = *game_state.get_player_for_round("bob", 0).unwrap().robots.keys().next().unwrap();
+        game_state.get_robot_for_current_round_by_player_id_and_robot_id("alice", &alice_robot_ids[1]).unwrap().health = 0;
+        pick_up(&mut game_state, "alice", None);
+        pick_up(&mut game_state, "alice", Some(bob_robot_id));
+        pick_up(&mut game_state, "alice", Some(alice_robot_ids[1]));
+        pick_up(&mut game_state, "alice", Some(alice_robot_ids[0]));
+
+        handle_pickup_commands(&mut game_state);
+        let alice = game_state.get_player_for_round("alice", 0).unwrap();
+        let reasons: Vec<&RejectionReason> = alice.rejected_commands.iter().map(|rejection| &rejection.reason).collect();
+        assert_eq!(reasons, vec![&RejectionReason::RobotNotFound, &RejectionReason::RobotNotFound, &RejectionReason::RobotDead]);
+        assert_eq!(alice.robots[&alice_robot_ids[0]].inventory[&Resource::COAL], 10);
+    }
+
+    #[test]
+    fn test_pick_up_loot_takes_most_valuable_resources_first() {
+        let mut robot = Robot::new(Uuid::new_v4(), Uuid::new_v4());
+        let mut loot = HashMap::from([(Resource::COAL, 10), (Resource::GOLD, 3)]);
+        assert_eq!(pick_up_loot(&mut robot, &mut loot, 5), 5);
+        assert_eq!(robot.inventory[&Resource::GOLD], 3);
+        assert_eq!(robot.inventory[&Resource::COAL], 2);
+        assert_eq!(loot, HashMap::from([(Resource::COAL, 8)]));
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::{HashMap, HashSet};
+
+use tracing::log::info;
+use uuid::Uuid;
+
+use crate::game::game_rules::MovementCostModel;
+use crate::game::game_state::{GameMap, GameState};
+use crate::planet::pathfinding::find_cheapest_path;
+use crate::planet::planet::Planet;
+use crate::player::PlayerStatus;
+use crate::robot::robot::Robot;
+use crate::robot::standing_order::StandingOrder;
+use crate::trading::external::command::Command;
+use crate::trading::external::command_object::CommandObject;
+use crate::trading::external::command_type::CommandType;
+
+/// Turns the standing orders of the robots of active players into commands for the current round.
+/// Robots which already got a command for this round keep their order for the following rounds.
+pub fn expand_standing_orders(game_state: &mut GameState) {
+    let game_id = game_state.game_id.to_string();
+    let current_round = game_state.current_round;
+    let movement_cost_model = game_state.rules.movement_cost.clone();
+    let known_planets_per_player: HashMap<String, HashSet<Uuid>> = game_state.participating_players.iter()
+        .map(|player_name| (player_name.clone(), game_state.get_known_planets(player_name, current_round)))
+        .collect();
+    let round_state = game_state.round_states.get_mut(&current_round).unwrap();
+    let map = &round_state.map;
+
+    for player in round_state.player_name_player_map.values_mut() {
+        if player.status != PlayerStatus::Active {
+            continue;
+        }
+        // A MOVE_TO command is carried out as a standing order, so the robot keeps going in the following rounds
+        if let Some(move_to_commands) = player.commands.get_mut(&CommandType::MOVE_TO) {
+            for command in move_to_commands.drain(..) {
+                let robot_id = command.command_object.robot_id.expect("Robot id is required");
+                let destination = command.command_object.target_id.expect("Target id is required for move to commands");
+                if let Some(robot) = player.robots.get_mut(&robot_id) {
+                    robot.standing_order = Some(StandingOrder::MoveTo { destination });
+                }
+            }
+        }
+        let known_planets = known_planets_per_player.get(&player.player_name).cloned().unwrap_or_default();
+        let robots_with_commands: HashSet<Uuid> = player.commands.values().flatten().filter_map(|command| command.command_object.robot_id).collect();
+        let mut expanded_commands = Vec::new();
+        for robot in player.robots.values_mut() {
+            if robots_with_commands.contains(&robot.robot_id) || !robot.is_alive() {
+                continue;
+            }
+            let Some(mut standing_order) = robot.standing_order.take() else { continue };
+            match get_next_command_for_standing_order(robot, &mut standing_order, map, &movement_cost_model, &known_planets) {
+                Some((command_type, target_id)) => {
+                    expanded_commands.push(Command {
+                        player_name: player.player_name.clone(),
+                        game_id: game_id.clone(),
+                        command_type,
+                        command_object: CommandObject { robot_id: Some(robot.robot_id), planet_id: None, target_id, item_name: None, item_quantity: None },
+                    });
+                    robot.standing_order = Some(standing_order);
+                }
+                None => info!("Robot {} completed its standing order {:?}", robot.robot_id, standing_order),
+            }
+        }
+        for command in expanded_commands {
+            player.commands.entry(command.command_type).or_default().push_back(command);
+        }
+    }
+}
+
+/// Returns the command type and target of the command for this round, or None if the order is complete.
+fn get_next_command_for_standing_order(robot: &Robot, standing_order: &mut StandingOrder, map: &GameMap, movement_cost_model: &MovementCostModel, known_planets: &HashSet<Uuid>) -> Option<(CommandType, Option<Uuid>)> {
+    let current_planet = map.get_planet(&robot.planet_id)?;
+    match standing_order {
+        StandingOrder::FollowPath { path } => {
+            while path.front() == Some(&robot.planet_id) {
+                path.pop_front();
+            }
+            let next_planet_id = *path.front()?;
+            if !current_planet.neighbours.values().any(|neighbour_id| *neighbour_id == next_planet_id) {
+                info!("Robot {} cancelled its path because planet {} is not a neighbour of planet {}", robot.robot_id, next_planet_id, robot.planet_id);
+                return None;
+            }
+            Some(get_step_towards(robot, current_planet, map.get_planet(&next_planet_id)?, movement_cost_model))
+        }
+        StandingOrder::MoveTo { destination } => {
+            if robot.planet_id == *destination {
+                return None;
+            }
+            let Some(path) = find_cheapest_path(map, movement_cost_model, Some(known_planets), robot.planet_id, *destination) else {
+                info!("Robot {} cancelled moving to planet {} because there is no known route", robot.robot_id, destination);
+                return None;
+            };
+            Some(get_step_towards(robot, current_planet, map.get_planet(&path.planets[1])?, movement_cost_model))
+        }
+        StandingOrder::MineThenSell => {
+            let can_mine_resources = match &current_planet.resources {
+                Some((resource, amount)) => *amount > 0 && robot.get_mineable_resources().contains(resource),
+                None => false,
+            };
+            if !robot.is_storage_full() && (can_mine_resources || !current_planet.loot.is_empty()) {
+                return Some((CommandType::MINING, Some(robot.planet_id)));
+            }
+            if robot.get_used_storage_space() > 0 {
+                return Some((CommandType::SELLING, None));
+            }
+            None
+        }
+        StandingOrder::RegenerateUntilFull => {
+            if robot.energy < robot.levels.get_energy_for_level() {
+                return Some((CommandType::REGENERATE, None));
+            }
+            None
+        }
+    }
+}
+
+/// Moves to the neighbouring planet, or regenerates if the robot lacks the energy to do so.
+fn get_step_towards(robot: &Robot, current_planet: &Planet, next_planet: &Planet, movement_cost_model: &MovementCostModel) -> (CommandType, Option<Uuid>) {
+    if robot.energy < movement_cost_model.get_movement_cost(current_planet, next_planet) {
+        return (CommandType::REGENERATE, None);
+    }
+    (CommandType::MOVEMENT, Some(next_planet.planet_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use crate::game::game_rules::GameRules;
+    use crate::planet::direction::Direction;
+    use crate::planet::planet::Planet;
+    use crate::player::PlayerState;
+
+    use super::*;
+
+    #[test]
+    fn test_standing_orders_are_expanded_until_complete() {
+        let mut start = Planet::new(Uuid::new_v4(), 1);
+        let mut destination = Planet::new(Uuid::new_v4(), 1);
+        start.set_neighbour(Direction::EAST, destination.planet_id);
+        destination.set_neighbour(Direction::WEST, start.planet_id);
+        let (start_id, destination_id) = (start.planet_id, destination.planet_id);
+        let mut game_state = GameState::new(Uuid::new_v4(), 10, 1, vec![vec![Some(start)], vec![Some(destination)]], GameRules::default(), 0);
+        let mut player = PlayerState::new("alice".to_string(), 500);
+        let mut walking_robot = Robot::new(Uuid::new_v4(), start_id);
+        walking_robot.standing_order = Some(StandingOrder::FollowPath { path: VecDeque::from([start_id, destination_id]) });
+        let mut resting_robot = Robot::new(Uuid::new_v4(), start_id);
+        resting_robot.standing_order = Some(StandingOrder::RegenerateUntilFull);
+        let (walking_robot_id, resting_robot_id) = (walking_robot.robot_id, resting_robot.robot_id);
+        player.robots.insert(walking_robot_id, walking_robot);
+        player.robots.insert(resting_robot_id, resting_robot);
+        game_state.round_states.get_mut(&0).unwrap().player_name_player_map.insert("alice".to_string(), player);
+
+        expand_standing_orders(&mut game_state);
+        let player = game_state.get_player_for_current_round_as_mut("alice").unwrap();
+        let movement_command = player.commands[&CommandType::MOVEMENT].front().unwrap();
+        assert_eq!(movement_command.command_object.robot_id, Some(walking_robot_id));
+        assert_eq!(movement_command.command_object.target_id, Some(destination_id));
+        assert_eq!(player.robots[&walking_robot_id].standing_order, Some(StandingOrder::FollowPath { path: VecDeque::from([destination_id]) }));
+        // The resting robot already has full energy
+        assert!(player.commands[&CommandType::REGENERATE].is_empty());
+        assert_eq!(player.robots[&resting_robot_id].standing_order, None);
+
+        player.commands.values_mut().for_each(VecDeque::clear);
+        player.robots.get_mut(&walking_robot_id).unwrap().planet_id = destination_id;
+        expand_standing_orders(&mut game_state);
+        let player = game_state.get_player_for_current_round_as_mut("alice").unwrap();
+        assert!(player.commands[&CommandType::MOVEMENT].is_empty());
+        assert_eq!(player.robots[&walking_robot_id].standing_order, None);
+    }
+}
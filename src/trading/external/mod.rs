@@ -2,6 +2,8 @@ pub mod command;
 
 pub mod command_object;
 
+pub mod command_rejection;
+
 pub mod command_type;
 
 pub mod handler;